@@ -1,5 +1,5 @@
 ﻿using System;
-using System.IO;
+using System.Linq;
 using System.Threading.Tasks;
 using UnityEngine;
 using Wizards.Mtga.FrontDoorModels;
@@ -54,24 +54,37 @@ namespace mtga_datacollector
 
     private void LoadDatabase()
     {
-      _logger.Info($"[card-db]{WrapperController.Instance.CardDatabase}");
       try
       {
-        _logger.Info($"[card-db]Trying to write MID file");
-
-        File.WriteAllText("f:\\temp\\mtga\\MID.txt", JsonConvert.SerializeObject(WrapperController.Instance.CardDatabase.GetPrintingsByExpansion("MID"), new JsonSerializerSettings
+        var cardDatabase = WrapperController.Instance.CardDatabase;
+        var cards = cardDatabase.DatabaseUtilities.GetAllPrintings().Values
+          .Select(card => new
+          {
+            GrpId = card.GrpId,
+            TitleId = card.TitleId,
+            Title = cardDatabase.GreLocProvider.GetLocalizedText(card.TitleId, "en-US"),
+            Set = card.ExpansionCode,
+            DigitalReleaseSet = card.DigitalReleaseSet,
+            Rarity = card.Rarity.ToString(),
+            CollectorNumber = card.CollectorNumber,
+            IsDigitalOnly = card.IsDigitalOnly,
+            IsRebalanced = card.IsRebalanced,
+            IsPrimaryCard = card.IsPrimaryCard,
+            IsToken = card.IsToken,
+          })
+          .ToList();
+
+        LogEntry cardDb = new LogEntry
         {
-          Formatting = Formatting.Indented,
-          ReferenceLoopHandling = ReferenceLoopHandling.Serialize,
-          PreserveReferencesHandling = PreserveReferencesHandling.Objects,
-        }));
-
-        _logger.Info($"[card-db]MID file written");
+          Attachment = cards,
+          Timestamp = String.Format($"{DateTime.Now:O}"),
+        };
 
+        _logger.Info($"[card-db]{JsonConvert.SerializeObject(cardDb)}");
       }
       catch (Exception e)
       {
-        _logger.Info($"[card-db]Something wrong: {e}");
+        _logger.Info($"[initialization]Could not log the card database: {e}");
       }
 
       _databaseLoaded = true;
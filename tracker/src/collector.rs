@@ -0,0 +1,391 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+pub mod model;
+mod tailer;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+use model::{ArenaCard, Collection, Inventory, InventoryUpdate};
+use tailer::LogTailer;
+
+/// A parsed collector event, or the reason why a tagged line could not be parsed.
+pub type EventResult = Result<CollectorEvent, Box<dyn Error>>;
+
+/// The envelope the data collector wraps its periodic payloads in before serializing them
+/// into the log.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogEntry<T> {
+    pub timestamp: DateTime<FixedOffset>,
+    pub attachment: T,
+}
+
+#[derive(Debug)]
+pub struct AccountInfo {
+    pub user_id: String,
+    pub screen_name: String,
+}
+
+impl TryFrom<&str> for AccountInfo {
+    type Error = Box<dyn Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        // The collector logs an anonymous C# object: { UserId = ..., ScreenName = ... }
+        let fields = value
+            .trim()
+            .strip_prefix('{')
+            .and_then(|v| v.strip_suffix('}'))
+            .ok_or("Account info is not enclosed in braces")?;
+        let fields = fields
+            .trim()
+            .strip_prefix("UserId = ")
+            .ok_or("Account info is missing the user id")?;
+        let (user_id, screen_name) = fields
+            .split_once(", ScreenName = ")
+            .ok_or("Account info is missing the screen name")?;
+
+        Ok(AccountInfo {
+            user_id: user_id.trim().to_string(),
+            screen_name: screen_name.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum CollectorEvent {
+    Initialization(String),
+    AccountInfo(AccountInfo),
+    LoginState(String),
+    Collection(LogEntry<Collection>),
+    Inventory(LogEntry<Inventory>),
+    InventoryUpdate(LogEntry<InventoryUpdate>),
+    CardDatabase(LogEntry<Vec<ArenaCard>>),
+}
+
+impl CollectorEvent {
+    /// Parses a single line of the Player.log. Lines that do not carry one of the data
+    /// collector's tags are not an error, they result in `None`.
+    pub fn from_line(line: &str) -> Result<Option<CollectorEvent>, Box<dyn Error>> {
+        let (tag, payload) = match CollectorTag::find(line) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+
+        let event = match tag {
+            CollectorTag::Initialization => CollectorEvent::Initialization(payload.to_string()),
+            CollectorTag::AccountInfo => CollectorEvent::AccountInfo(payload.try_into()?),
+            CollectorTag::LoginState => CollectorEvent::LoginState(payload.trim().to_string()),
+            CollectorTag::Collection => CollectorEvent::Collection(serde_json::from_str(payload)?),
+            CollectorTag::Inventory => CollectorEvent::Inventory(serde_json::from_str(payload)?),
+            CollectorTag::InventoryUpdate => {
+                CollectorEvent::InventoryUpdate(serde_json::from_str(payload)?)
+            }
+            CollectorTag::CardDatabase => {
+                CollectorEvent::CardDatabase(serde_json::from_str(payload)?)
+            }
+        };
+
+        Ok(Some(event))
+    }
+}
+
+impl Display for CollectorEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollectorEvent::Initialization(message) => write!(f, "[initialization] {}", message),
+            CollectorEvent::AccountInfo(info) => {
+                write!(f, "[account-info] {} ({})", info.screen_name, info.user_id)
+            }
+            CollectorEvent::LoginState(state) => write!(f, "[loginstate] {}", state),
+            CollectorEvent::Collection(entry) => write!(
+                f,
+                "[collection] {}: {} cards, {} different",
+                entry.timestamp,
+                entry.attachment.iter().map(|(_, count)| count).sum::<u32>(),
+                entry.attachment.iter().count()
+            ),
+            CollectorEvent::Inventory(entry) => {
+                let inventory = &entry.attachment;
+                write!(
+                    f,
+                    "[inventory] {}: {} gold, {} gems, wildcards {}/{}/{}/{}, vault {:.1}%",
+                    entry.timestamp,
+                    inventory.gold,
+                    inventory.gems,
+                    inventory.wc_common,
+                    inventory.wc_uncommon,
+                    inventory.wc_rare,
+                    inventory.wc_mythic,
+                    inventory.vault_progress
+                )
+            }
+            CollectorEvent::InventoryUpdate(entry) => write!(
+                f,
+                "[inventory-update] {}: {}, {} cards added",
+                entry.timestamp,
+                entry
+                    .attachment
+                    .context
+                    .as_ref()
+                    .and_then(|context| context.source())
+                    .unwrap_or("unknown source"),
+                entry.attachment.delta.cards_added.len()
+            ),
+            CollectorEvent::CardDatabase(entry) => write!(
+                f,
+                "[card-db] {}: {} cards",
+                entry.timestamp,
+                entry.attachment.len()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum CollectorTag {
+    Initialization,
+    AccountInfo,
+    LoginState,
+    Collection,
+    Inventory,
+    InventoryUpdate,
+    CardDatabase,
+}
+
+impl CollectorTag {
+    /// Finds the first collector tag in the line and returns it with the rest of the line.
+    fn find(line: &str) -> Option<(CollectorTag, &str)> {
+        line.match_indices('[').find_map(|(start, _)| {
+            let rest = &line[start + 1..];
+            let end = rest.find(']')?;
+            let tag = CollectorTag::try_from(&rest[..end]).ok()?;
+            Some((tag, &rest[end + 1..]))
+        })
+    }
+}
+
+impl TryFrom<&str> for CollectorTag {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "initialization" => Ok(CollectorTag::Initialization),
+            "account-info" => Ok(CollectorTag::AccountInfo),
+            "loginstate" => Ok(CollectorTag::LoginState),
+            "collection" => Ok(CollectorTag::Collection),
+            "inventory" => Ok(CollectorTag::Inventory),
+            "inventory-update" => Ok(CollectorTag::InventoryUpdate),
+            "card-db" => Ok(CollectorTag::CardDatabase),
+            _ => Err("Could not match tag to any of the collector tags"),
+        }
+    }
+}
+
+/// Reads the data collector's events out of the Player.log.
+pub struct CollectorLog {
+    tailer: LogTailer,
+}
+
+impl CollectorLog {
+    pub fn open<P>(player_log_path: P) -> CollectorLog
+    where
+        P: AsRef<Path>,
+    {
+        CollectorLog {
+            tailer: LogTailer::new(player_log_path),
+        }
+    }
+
+    /// Returns the events logged since the previous call. Every tagged line produces an
+    /// entry, lines with a payload that could not be parsed produce an error entry so one
+    /// malformed line does not stop the rest of the log from being read.
+    pub fn read_events(&mut self) -> Result<Vec<EventResult>, Box<dyn Error>> {
+        let events = self
+            .tailer
+            .read_lines()?
+            .iter()
+            .filter_map(|line| CollectorEvent::from_line(line).transpose())
+            .collect();
+        Ok(events)
+    }
+
+    /// Keeps reading the log until the handler returns an error.
+    pub fn follow<F>(
+        &mut self,
+        poll_interval: Duration,
+        mut handler: F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(EventResult) -> Result<(), Box<dyn Error>>,
+    {
+        loop {
+            for event in self.read_events()? {
+                handler(event)?;
+            }
+            thread::sleep(poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const PLAYER_LOG: &str = include_str!("../tests/fixtures/Player.log");
+
+    fn event(line: &str) -> CollectorEvent {
+        CollectorEvent::from_line(line)
+            .expect("the line should parse")
+            .expect("the line should carry a collector tag")
+    }
+
+    fn fixture_line(tag: &str) -> &'static str {
+        PLAYER_LOG
+            .lines()
+            .find(|line| line.contains(tag))
+            .expect("the fixture should have the tag")
+    }
+
+    #[test]
+    fn from_line_skips_lines_without_a_collector_tag() {
+        assert!(
+            CollectorEvent::from_line("Mono path[0] = 'C:/Program Files'")
+                .unwrap()
+                .is_none()
+        );
+        assert!(CollectorEvent::from_line(
+            "[UnityCrossThreadLogger][Matchmaking] no collector tag"
+        )
+        .unwrap()
+        .is_none());
+        assert!(CollectorEvent::from_line("").unwrap().is_none());
+    }
+
+    #[test]
+    fn from_line_parses_the_plain_payloads() {
+        match event(fixture_line("[initialization]")) {
+            CollectorEvent::Initialization(message) => {
+                assert!(message.starts_with("Initialization started at"))
+            }
+            other => panic!("unexpected event {}", other),
+        }
+        match event(fixture_line("[account-info]")) {
+            CollectorEvent::AccountInfo(info) => {
+                assert_eq!(info.user_id, "ABCDEF123");
+                assert_eq!(info.screen_name, "Ojji#12345");
+            }
+            other => panic!("unexpected event {}", other),
+        }
+        match event("[MTGADataCollector][loginstate] LoggedOut ") {
+            CollectorEvent::LoginState(state) => assert_eq!(state, "LoggedOut"),
+            other => panic!("unexpected event {}", other),
+        }
+    }
+
+    #[test]
+    fn from_line_parses_the_json_payloads() {
+        match event(fixture_line("[collection]")) {
+            CollectorEvent::Collection(entry) => {
+                assert_eq!(
+                    entry.timestamp.to_rfc3339(),
+                    "2022-10-10T21:36:12.123456700+02:00"
+                );
+                let cards = entry.attachment.iter().collect::<HashMap<_, _>>();
+                assert_eq!(cards, HashMap::from([(75000, 4), (75001, 2)]));
+            }
+            other => panic!("unexpected event {}", other),
+        }
+        match event(fixture_line("[inventory]")) {
+            CollectorEvent::Inventory(entry) => {
+                let inventory = entry.attachment;
+                assert_eq!(inventory.gold, 1000);
+                assert_eq!(inventory.wc_rare, 3);
+                // the fields the client left out fall back to their defaults
+                assert_eq!(inventory.wc_mythic, 0);
+                assert_eq!(inventory.boosters.len(), 1);
+                assert_eq!(inventory.boosters[0].collation_id, 100025);
+                assert_eq!(inventory.boosters[0].count, 3);
+            }
+            other => panic!("unexpected event {}", other),
+        }
+    }
+
+    #[test]
+    fn from_line_parses_both_forms_of_the_inventory_update_context() {
+        let updates = PLAYER_LOG
+            .lines()
+            .filter(|line| line.contains("[inventory-update]"))
+            .map(event)
+            .map(|event| match event {
+                CollectorEvent::InventoryUpdate(entry) => entry.attachment,
+                other => panic!("unexpected event {}", other),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(updates.len(), 2);
+        let context = updates[0].context.as_ref().unwrap();
+        assert_eq!(context.source(), Some("BoosterOpen"));
+        assert_eq!(updates[0].delta.cards_added, [75000, 75001]);
+        assert_eq!(updates[0].delta.wc_uncommon_delta, 1);
+        let context = updates[1].context.as_ref().unwrap();
+        assert_eq!(context.source(), Some("QuestReward"));
+        assert_eq!(updates[1].delta.gold_delta, 500);
+    }
+
+    #[test]
+    fn from_line_fails_on_a_malformed_payload() {
+        assert!(
+            CollectorEvent::from_line(r#"[MTGADataCollector][inventory]{"Timestamp":"#).is_err()
+        );
+        assert!(
+            CollectorEvent::from_line("[MTGADataCollector][account-info]{ UserId = ABC }").is_err()
+        );
+        assert!(
+            CollectorEvent::from_line("[MTGADataCollector][account-info]UserId = ABC").is_err()
+        );
+    }
+
+    #[test]
+    fn from_line_parses_the_client_card_database() {
+        let line = include_str!("../tests/fixtures/card-db.log");
+        let entry = match event(line) {
+            CollectorEvent::CardDatabase(entry) => entry,
+            other => panic!("unexpected event {}", other),
+        };
+        let cards = entry.attachment;
+        assert_eq!(cards.len(), 5);
+
+        let rebalanced = &cards[1];
+        assert_eq!(rebalanced.grp_id, 80002);
+        assert_eq!(rebalanced.title.as_deref(), Some("A-S\u{e9}ance"));
+        assert_eq!(rebalanced.set, "DMU");
+        assert_eq!(rebalanced.collector_number, "10");
+        assert!(rebalanced.is_digital_only && rebalanced.is_rebalanced);
+
+        let arena_only = &cards[2];
+        assert_eq!(arena_only.rarity, "MythicRare");
+        assert!(arena_only.is_digital_only && !arena_only.is_rebalanced);
+        assert!(cards[3].is_token);
+        // older clients do not log IsToken
+        assert!(!cards[4].is_token);
+    }
+
+    #[test]
+    fn read_events_reports_the_malformed_lines_and_keeps_reading() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/Player.log");
+        let events = CollectorLog::open(path).read_events().unwrap();
+
+        assert_eq!(events.len(), 10);
+        assert_eq!(events.iter().filter(|event| event.is_err()).count(), 1);
+        assert!(matches!(
+            events.last(),
+            Some(Ok(CollectorEvent::Collection(_)))
+        ));
+    }
+}
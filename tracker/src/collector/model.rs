@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// # Collection
+/// The `InventoryManager.Cards` dictionary of the client, the number of copies the player owns of each card,
+/// keyed by the Arena card id (`grpId`).
+///
+/// ## Example Json object:
+/// ```json
+/// {
+///   "68309": 4,
+///   "75034": 1
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Collection(HashMap<u32, u32>);
+
+impl Collection {
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.0.iter().map(|(&arena_id, &count)| (arena_id, count))
+    }
+}
+
+impl FromIterator<(u32, u32)> for Collection {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, u32)>,
+    {
+        Collection(iter.into_iter().collect())
+    }
+}
+
+/// # Inventory
+/// The `InventoryManager.Inventory` object of the client, everything the player owns besides the cards.
+///
+/// Only the fields the tracker uses are described here, anything else the client sends is ignored, and
+/// missing fields fall back to their default values, so a game patch that reshapes the object does not stop
+/// the ingestion.
+///
+/// ## Example Json object:
+/// ```json
+/// {
+///   "wcCommon": 21,
+///   "wcUncommon": 29,
+///   "wcRare": 11,
+///   "wcMythic": 7,
+///   "gold": 3575,
+///   "gems": 1680,
+///   "draftTokens": 0,
+///   "sealedTokens": 0,
+///   "wcTrackPosition": 2,
+///   "vaultProgress": 15.2,
+///   "boosters": [
+///     {
+///       "collationId": 100025,
+///       "count": 3
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Inventory {
+    /// The amount of gold.
+    pub gold: i32,
+
+    /// The amount of gems.
+    pub gems: i32,
+
+    /// The number of common wildcards.
+    pub wc_common: i32,
+
+    /// The number of uncommon wildcards.
+    pub wc_uncommon: i32,
+
+    /// The number of rare wildcards.
+    pub wc_rare: i32,
+
+    /// The number of mythic rare wildcards.
+    pub wc_mythic: i32,
+
+    /// The progress of the vault in percent. The vault opens at 100.
+    pub vault_progress: f64,
+
+    /// The position on the wildcard track, which decides when the next booster gives a wildcard.
+    pub wc_track_position: i32,
+
+    /// The number of draft tokens.
+    pub draft_tokens: i32,
+
+    /// The number of sealed tokens.
+    pub sealed_tokens: i32,
+
+    /// The unopened boosters.
+    pub boosters: Vec<Booster>,
+}
+
+/// # Booster
+/// A stack of unopened boosters of a single kind.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Booster {
+    /// The id of the collation the booster is opened from. Arena uses these ids to identify the booster kind,
+    /// they encode the set of the booster.
+    pub collation_id: u32,
+
+    /// The set code of the booster, if the client sends one.
+    pub set_code: Option<String>,
+
+    /// The number of boosters.
+    pub count: i32,
+}
+
+/// # Inventory Update
+/// A `ClientInventoryUpdateReportItem`, sent by the client every time the inventory changes: a booster is
+/// opened, a wildcard is redeemed, a reward is granted, etc.
+///
+/// ## Example Json object:
+/// ```json
+/// {
+///   "context": {
+///     "source": "BoosterOpen",
+///     "sourceId": "100025"
+///   },
+///   "delta": {
+///     "gemsDelta": 0,
+///     "goldDelta": 0,
+///     "boosterDelta": [
+///       {
+///         "collationId": 100025,
+///         "count": -1
+///       }
+///     ],
+///     "cardsAdded": [79532, 79611, 79597],
+///     "wcCommonDelta": 0,
+///     "wcUncommonDelta": 1,
+///     "wcRareDelta": 0,
+///     "wcMythicDelta": 0,
+///     "vaultProgressDelta": 0.0
+///   },
+///   "aetherizedCards": [
+///     {
+///       "grpId": 79532,
+///       "addedToInventory": false,
+///       "vaultProgress": 0.1,
+///       "goldAwarded": 0,
+///       "gemsAwarded": 0
+///     }
+///   ],
+///   "xpGained": 0
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InventoryUpdate {
+    /// What caused the update.
+    pub context: Option<InventoryUpdateContext>,
+
+    /// The changes to the inventory.
+    pub delta: InventoryDelta,
+
+    /// The cards that were granted but turned into vault progress, gold or gems instead of being added to the
+    /// collection.
+    pub aetherized_cards: Vec<AetherizedCard>,
+
+    /// The experience gained with the update.
+    pub xp_gained: i32,
+}
+
+/// The source of an inventory update. Depending on the client version it is either serialized as the name of
+/// the source only, or as an object with the source and its id.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum InventoryUpdateContext {
+    Source(String),
+    Detailed {
+        source: Option<String>,
+        #[serde(rename = "sourceId")]
+        source_id: Option<String>,
+    },
+}
+
+impl InventoryUpdateContext {
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            InventoryUpdateContext::Source(source) => Some(source),
+            InventoryUpdateContext::Detailed { source, .. } => source.as_deref(),
+        }
+    }
+}
+
+/// # Inventory Delta
+/// The difference an inventory update made.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InventoryDelta {
+    /// The change in gems.
+    pub gems_delta: i32,
+
+    /// The change in gold.
+    pub gold_delta: i32,
+
+    /// The change in the boosters.
+    pub booster_delta: Vec<Booster>,
+
+    /// The Arena ids of the cards added to the collection. A card added multiple times is listed multiple
+    /// times.
+    pub cards_added: Vec<u32>,
+
+    /// The change in common wildcards.
+    pub wc_common_delta: i32,
+
+    /// The change in uncommon wildcards.
+    pub wc_uncommon_delta: i32,
+
+    /// The change in rare wildcards.
+    pub wc_rare_delta: i32,
+
+    /// The change in mythic rare wildcards.
+    pub wc_mythic_delta: i32,
+
+    /// The change in the vault progress, in percent.
+    pub vault_progress_delta: f64,
+
+    /// The change in draft tokens.
+    pub draft_tokens_delta: i32,
+
+    /// The change in sealed tokens.
+    pub sealed_tokens_delta: i32,
+}
+
+/// # Aetherized Card
+/// A card that was granted to the player over the playset limit, and was converted into something else.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AetherizedCard {
+    /// The Arena id of the card.
+    pub grp_id: u32,
+
+    /// True if the card was added to the collection anyway.
+    pub added_to_inventory: bool,
+
+    /// The vault progress the card was converted into.
+    pub vault_progress: f64,
+
+    /// The gold the card was converted into.
+    pub gold_awarded: i32,
+
+    /// The gems the card was converted into.
+    pub gems_awarded: i32,
+}
+
+/// # Arena Card
+/// A card printing of the client's card database, as the collector logs it. Unlike the Scryfall data, it has
+/// every card the client knows, including the Alchemy rebalanced and Arena-only cards.
+///
+/// ## Example Json object:
+/// ```json
+/// {
+///   "GrpId": 79560,
+///   "TitleId": 434412,
+///   "Title": "A-Lier, Disciple of the Drowned",
+///   "Set": "MID",
+///   "DigitalReleaseSet": "",
+///   "Rarity": "MythicRare",
+///   "CollectorNumber": "59",
+///   "IsDigitalOnly": true,
+///   "IsRebalanced": true,
+///   "IsPrimaryCard": true,
+///   "IsToken": false
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ArenaCard {
+    /// The Arena id of the card.
+    pub grp_id: u32,
+
+    /// The id of the localized card name.
+    pub title_id: u32,
+
+    /// The English name of the card.
+    pub title: Option<String>,
+
+    /// The set code the client uses, which is not always the Scryfall one.
+    pub set: String,
+
+    /// The digital set the card was released in, if it is not the set of the printing.
+    pub digital_release_set: Option<String>,
+
+    /// One of `Land`, `Common`, `Uncommon`, `Rare` and `MythicRare`.
+    pub rarity: String,
+
+    /// The collector number of the card.
+    pub collector_number: String,
+
+    /// True if the card only exists on Arena.
+    pub is_digital_only: bool,
+
+    /// True if this is the Alchemy rebalanced version of a card.
+    pub is_rebalanced: bool,
+
+    /// False for the alternate printings of a card.
+    pub is_primary_card: bool,
+
+    /// True for tokens, which Scryfall has no Arena ids for.
+    pub is_token: bool,
+}
@@ -0,0 +1,236 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const FINGERPRINT_LEN: usize = 512;
+
+/// Follows a growing text file, such as the Unity `Player.log`, and hands out the lines
+/// appended since the previous read.
+///
+/// The file is reopened by path on every read, so the tailer keeps working when the game
+/// restarts and Unity moves the old log out of the way. A new file is detected when the
+/// file shrinks below the current read position, when its creation time changes, or when
+/// the last bytes read no longer match the ones now at their place; in all of these cases
+/// reading starts over from the beginning of the file. The start of the file cannot tell
+/// the sessions apart, as every Unity log starts with the same header.
+pub struct LogTailer {
+    path: PathBuf,
+    position: u64,
+    created: Option<SystemTime>,
+    fingerprint: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl LogTailer {
+    pub fn new<P>(path: P) -> LogTailer
+    where
+        P: AsRef<Path>,
+    {
+        LogTailer {
+            path: path.as_ref().to_path_buf(),
+            position: 0,
+            created: None,
+            fingerprint: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the complete lines written since the last call. An unfinished last line is
+    /// kept back until its line break arrives. A missing file is not an error, it simply
+    /// yields no lines, since the log does not exist for a moment while it is rotated.
+    pub fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let metadata = file.metadata()?;
+        let created = metadata.created().ok();
+
+        if self.is_new_file(&mut file, metadata.len(), created)? {
+            self.position = 0;
+            self.fingerprint.clear();
+            self.pending.clear();
+        }
+        self.created = created;
+
+        file.seek(SeekFrom::Start(self.position))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        self.position += data.len() as u64;
+        self.fingerprint.extend_from_slice(&data);
+        let excess = self.fingerprint.len().saturating_sub(FINGERPRINT_LEN);
+        self.fingerprint.drain(..excess);
+        self.pending.extend_from_slice(&data);
+
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        for (idx, _) in self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, &byte)| byte == b'\n')
+        {
+            let line = String::from_utf8_lossy(&self.pending[line_start..idx]);
+            lines.push(line.trim_end_matches('\r').to_string());
+            line_start = idx + 1;
+        }
+        self.pending.drain(..line_start);
+
+        Ok(lines)
+    }
+
+    fn is_new_file(
+        &self,
+        file: &mut File,
+        len: u64,
+        created: Option<SystemTime>,
+    ) -> io::Result<bool> {
+        if len < self.position {
+            return Ok(true);
+        }
+
+        if let (Some(previous), Some(current)) = (self.created, created) {
+            if previous != current {
+                return Ok(true);
+            }
+        }
+
+        // the fingerprint holds the bytes right before the read position
+        file.seek(SeekFrom::Start(
+            self.position - self.fingerprint.len() as u64,
+        ))?;
+        let mut fingerprint = Vec::with_capacity(self.fingerprint.len());
+        file.by_ref()
+            .take(self.fingerprint.len() as u64)
+            .read_to_end(&mut fingerprint)?;
+        Ok(fingerprint != self.fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use super::*;
+
+    /// A log file in the temporary directory, removed again when the test ends.
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new(name: &str) -> TempLog {
+            let path = std::env::temp_dir().join(format!(
+                "tracker-tailer-{}-{}.log",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_file(&path);
+            TempLog(path)
+        }
+
+        fn write(&self, content: &str) {
+            fs::write(&self.0, content).unwrap();
+        }
+
+        fn append(&self, content: &str) {
+            let mut file = OpenOptions::new().append(true).open(&self.0).unwrap();
+            file.write_all(content.as_bytes()).unwrap();
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_only_the_appended_lines() {
+        let log = TempLog::new("append");
+        log.write("first\r\nsecond\n");
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap(), ["first", "second"]);
+        assert!(tailer.read_lines().unwrap().is_empty());
+
+        log.append("third\n");
+        assert_eq!(tailer.read_lines().unwrap(), ["third"]);
+    }
+
+    #[test]
+    fn keeps_an_unfinished_line_until_its_line_break() {
+        let log = TempLog::new("unfinished");
+        log.write("first\nsec");
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap(), ["first"]);
+
+        log.append("ond\n");
+        assert_eq!(tailer.read_lines().unwrap(), ["second"]);
+    }
+
+    #[test]
+    fn starts_over_when_the_file_is_truncated() {
+        let log = TempLog::new("truncated");
+        log.write("a long first line\nanother line\n");
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap().len(), 2);
+
+        log.write("a long first line\n");
+        assert_eq!(tailer.read_lines().unwrap(), ["a long first line"]);
+    }
+
+    #[test]
+    fn starts_over_when_the_file_is_rotated() {
+        let log = TempLog::new("rotated");
+        log.write("old session\n");
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap(), ["old session"]);
+
+        // the game moves the old log away, so for a moment there is none, then it writes a longer new one
+        fs::remove_file(&log.0).unwrap();
+        assert!(tailer.read_lines().unwrap().is_empty());
+        log.write("new session\nsecond line\n");
+        assert_eq!(tailer.read_lines().unwrap(), ["new session", "second line"]);
+    }
+
+    #[test]
+    fn starts_over_when_a_session_with_the_same_header_is_as_long() {
+        let header =
+            "Mono path[0] = 'C:/Program Files/Wizards of the Coast/MTGA/MTGA_Data/Managed'\n"
+                .repeat(10);
+        let log = TempLog::new("same-header");
+        log.write(&format!(
+            "{}Initialization started at 2022-10-10T21:35:12\n",
+            header
+        ));
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap().len(), 11);
+
+        log.write(&format!(
+            "{}Initialization started at 2022-10-11T09:12:45\nfirst event\n",
+            header
+        ));
+        let lines = tailer.read_lines().unwrap();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(
+            lines[10..],
+            [
+                "Initialization started at 2022-10-11T09:12:45",
+                "first event"
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_the_unfinished_line_of_the_rotated_file() {
+        let log = TempLog::new("rotated-unfinished");
+        log.write("old session\nunfinished");
+        let mut tailer = LogTailer::new(&log.0);
+        assert_eq!(tailer.read_lines().unwrap(), ["old session"]);
+
+        log.write("new session started\n");
+        assert_eq!(tailer.read_lines().unwrap(), ["new session started"]);
+    }
+}
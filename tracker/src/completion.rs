@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use serde::Serialize;
+
+use crate::collector::model::Collection;
+use crate::mtgadb::model::Rarity;
+use crate::mtgadb::{MtgaDb, SetFilter};
+
+/// The number of copies of a card a constructed deck can have.
+const PLAYSET: u32 = 4;
+
+/// How complete the playsets of the cards of a set are, for one rarity of the booster or the non-booster
+/// cards of the set.
+#[derive(Serialize)]
+pub struct Completion {
+    pub set: String,
+    /// True for the cards found in boosters.
+    pub booster: bool,
+    pub rarity: Rarity,
+    /// The number of different cards.
+    pub cards: u32,
+    /// The number of cards with at least a playset owned.
+    pub playsets: u32,
+    /// The number of copies owned, counting at most a playset of each card.
+    pub copies: u32,
+    /// The number of copies of all the playsets.
+    pub total_copies: u32,
+}
+
+impl Completion {
+    fn percent(&self) -> f64 {
+        self.copies as f64 * 100.0 / self.total_copies.max(1) as f64
+    }
+}
+
+/// Counts the owned playsets of the cards in the sets, ordered by set, booster cards first, then rarity.
+/// Basic lands are left out, as every account has all of them.
+pub fn completion(
+    database: &MtgaDb,
+    set_filter: &SetFilter,
+    collection: &Collection,
+) -> Vec<Completion> {
+    let owned = collection.iter().collect::<HashMap<_, _>>();
+
+    let mut completions = BTreeMap::new();
+    for (arena_id, card) in database.arena_cards() {
+        let basic_land = card
+            .type_line
+            .as_deref()
+            .is_some_and(|type_line| type_line.starts_with("Basic Land"));
+        if !set_filter.includes(&card.set) || basic_land {
+            continue;
+        }
+
+        let completion = completions
+            .entry((card.set.clone(), !card.booster, card.rarity.clone()))
+            .or_insert_with(|| Completion {
+                set: card.set.clone(),
+                booster: card.booster,
+                rarity: card.rarity.clone(),
+                cards: 0,
+                playsets: 0,
+                copies: 0,
+                total_copies: 0,
+            });
+        let copies = owned.get(arena_id).copied().unwrap_or(0).min(PLAYSET);
+        completion.cards += 1;
+        completion.playsets += (copies == PLAYSET) as u32;
+        completion.copies += copies;
+        completion.total_copies += PLAYSET;
+    }
+    completions.into_values().collect()
+}
+
+pub fn print_table(completions: &[Completion]) {
+    println!(
+        "{:<6} {:<11} {:<9} {:>5} {:>11} {:>13} {:>7}",
+        "Set", "Cards", "Rarity", "Count", "Playsets", "Copies", "Done"
+    );
+    let mut previous_set = None;
+    for completion in completions {
+        if previous_set.is_some_and(|set| set != completion.set) {
+            println!();
+        }
+        previous_set = Some(completion.set.as_str());
+        println!(
+            "{:<6} {:<11} {:<9} {:>5} {:>11} {:>13} {:>6.1}%",
+            completion.set,
+            if completion.booster {
+                "booster"
+            } else {
+                "non-booster"
+            },
+            completion.rarity.as_str(),
+            completion.cards,
+            format!("{}/{}", completion.playsets, completion.cards),
+            format!("{}/{}", completion.copies, completion.total_copies),
+            completion.percent()
+        );
+    }
+}
+
+pub fn print_json(completions: &[Completion]) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(completions)?);
+    Ok(())
+}
+
+pub fn print_csv(completions: &[Completion]) {
+    println!("set,booster,rarity,cards,playsets,copies,total_copies");
+    for completion in completions {
+        println!(
+            "{},{},{},{},{},{},{}",
+            completion.set,
+            completion.booster,
+            completion.rarity,
+            completion.cards,
+            completion.playsets,
+            completion.copies,
+            completion.total_copies
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::card;
+
+    fn database() -> MtgaDb {
+        MtgaDb::from_cards(
+            vec![
+                card(json!({ "arena_id": 1 })),
+                card(json!({ "arena_id": 2 })),
+                card(json!({ "arena_id": 3, "rarity": "rare" })),
+                card(json!({ "arena_id": 4, "booster": false })),
+                card(
+                    json!({ "arena_id": 5, "name": "Mountain", "type_line": "Basic Land — Mountain" }),
+                ),
+                card(json!({ "arena_id": 6, "set": "eld", "rarity": "mythic" })),
+            ],
+            vec![],
+        )
+    }
+
+    fn counts(completions: &[Completion]) -> Vec<(&str, bool, &str, u32, u32, u32, u32)> {
+        completions
+            .iter()
+            .map(|completion| {
+                (
+                    completion.set.as_str(),
+                    completion.booster,
+                    completion.rarity.as_str(),
+                    completion.cards,
+                    completion.playsets,
+                    completion.copies,
+                    completion.total_copies,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_the_playsets_per_set_and_rarity() {
+        let collection = [(1, 7), (2, 2), (4, 4), (5, 10), (6, 1), (99, 4)]
+            .into_iter()
+            .collect();
+        let completions = completion(&database(), &SetFilter::AllArenaSets, &collection);
+        assert_eq!(
+            counts(&completions),
+            [
+                ("eld", true, "mythic", 1, 0, 1, 4),
+                ("m19", true, "common", 2, 1, 6, 8),
+                ("m19", true, "rare", 1, 0, 0, 4),
+                ("m19", false, "common", 1, 1, 4, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_only_the_sets_of_the_filter() {
+        let collection = [(6, 4)].into_iter().collect();
+        let completions = completion(&database(), &SetFilter::only(["ELD"]), &collection);
+        assert_eq!(counts(&completions), [("eld", true, "mythic", 1, 1, 4, 4)]);
+        assert_eq!(completions[0].percent(), 100.0);
+    }
+}
@@ -1,52 +1,424 @@
 use std::{
     env::Args,
+    fs,
     path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{self, DeckFormat};
+use crate::inventory;
+use crate::mtgadb::{Query, SetFilter};
+
+const DEFAULT_COLLECTOR_PATH: &str =
+    "./mtga-datacollector/bin/x64/Release/netstandard2.1/mtga-datacollector.dll";
+const DEFAULT_DATABASE_PATH: &str = "./mtga-tracker.db";
+/// The set list `createdb` uses when none is given, built in so it does not depend on the working directory.
+const DEFAULT_SETS: &str = include_str!("../sets.txt");
+/// The prices Scryfall lists for the cards.
+const CURRENCIES: [&str; 6] = ["usd", "usd_foil", "usd_etched", "eur", "eur_foil", "tix"];
+
+/// Tracks the collection and inventory of an MTG Arena account.
+#[derive(Parser)]
+#[command(name = "tracker", version)]
+struct Cli {
+    /// The configuration file [default: config.toml in the mtga-tracker folder of the user's config directory]
+    #[arg(long = "config", global = true)]
+    config_path: Option<PathBuf>,
+
+    /// The tracker database holding the card database and the collection history [default: ./mtga-tracker.db]
+    #[arg(long = "db", global = true)]
+    database_path: Option<PathBuf>,
+
+    /// The preferred language of the card names and texts, a Scryfall language code like `de` or `ja`
+    /// [default: en]
+    #[arg(long = "lang", global = true)]
+    language: Option<String>,
+
+    /// The Arena account whose history is shown, by its user id [default: the account seen last]
+    #[arg(long = "account", global = true)]
+    account_id: Option<String>,
+
+    /// Print more details about what the tracker is doing
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Inject the data collector into the running MTGA process (the default command)
+    Inject {
+        /// The data collector assembly [default: the release build in ./mtga-datacollector]
+        collector_path: Option<PathBuf>,
+    },
+    /// Create the card database from a Scryfall bulk data file
+    #[command(name = "createdb")]
+    CreateDatabase {
+        /// The Scryfall bulk data file, e.g. the "Default Cards" or "All Cards" download
+        scryfall_db_path: PathBuf,
+
+        /// Comma separated set codes to include [default: the sets in tracker/sets.txt, built into the tracker]
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["sets_file", "all_sets"])]
+        sets: Option<Vec<String>>,
+
+        /// A file listing the set codes to include, separated by commas or whitespace
+        #[arg(long, conflicts_with = "all_sets")]
+        sets_file: Option<PathBuf>,
+
+        /// Include every set that has cards on Arena
+        #[arg(long)]
+        all_sets: bool,
+
+        /// A Player.log with the client's card database logged by the data collector, to add the cards that
+        /// have no Arena id on Scryfall
+        #[arg(long)]
+        arena_log: Option<PathBuf>,
+
+        /// Update the existing card database, changing only the cards that changed, and print what changed
+        #[arg(long)]
+        update: bool,
+
+        /// Only read the Scryfall database and report the sets, or with --update the changes, without writing
+        /// the card database
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Read the data collector's events from the Player.log into the collection history
+    Ingest {
+        /// The Player.log of MTGA [default: the one in the user profile]
+        player_log_path: Option<PathBuf>,
+
+        /// Keep following the log for new events
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Show the collection as it was at a point in time
+    Collection {
+        /// A date (YYYY-MM-DD, meaning the end of that day) or an RFC 3339 timestamp [default: now]
+        #[arg(value_parser = parse_date)]
+        at: Option<DateTime<Utc>>,
+    },
+    /// Show when a card was added to the collection
+    Acquired {
+        /// The Arena id of the card
+        arena_id: u32,
+    },
+    /// Look up cards in the card database by Arena id, name, set and collector number, or text
+    Card {
+        /// An Arena id, a card name, or with --set, a collector number
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Look up the printing with the collector number in this set
+        #[arg(long, conflicts_with = "text")]
+        set: Option<String>,
+
+        /// Search the names, types, Oracle and flavor texts, e.g. `"draw a card"` or `flying NOT flash`
+        #[arg(long)]
+        text: bool,
+    },
+    /// Search the card database with the Scryfall search syntax, e.g. `t:creature c:rg cmc<=3`
+    Search {
+        /// The search query, quote it to keep the shell from interpreting `<`, `>` and `"`
+        #[arg(required = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// List the cards of the collection history that are missing from the card database, with the likely
+    /// cause for each
+    Unmapped,
+    /// Show the owned cards that were banned, unbanned or otherwise changed legality in a format, as seen by
+    /// the card database refreshes
+    Bans {
+        /// The Scryfall name of the format, e.g. `historic` or `standard`
+        format: String,
+    },
+    /// Show how the prices of the cards in the configured sets moved between two card database builds
+    Prices {
+        /// The Scryfall price to compare
+        #[arg(long, default_value = "usd", value_parser = CURRENCIES)]
+        currency: String,
+
+        /// Compare with the prices of the last build on or before this date (YYYY-MM-DD) [default: the build
+        /// before the last one]
+        #[arg(long, value_parser = parse_date)]
+        since: Option<DateTime<Utc>>,
+
+        /// The number of cards to show, the biggest movers first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show the paper value of the cards owned on Arena, by the prices of the last card database build
+    Value {
+        /// The Scryfall price to use
+        #[arg(long, default_value = "usd", value_parser = CURRENCIES)]
+        currency: String,
+
+        /// The number of cards to show, the most valuable first
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show how many playsets of each rarity of the configured sets are owned, for the booster and the
+    /// non-booster cards
+    Completion {
+        /// The output format
+        #[arg(long, default_value = "table", value_parser = ["table", "json", "csv"])]
+        format: String,
+    },
+    /// Show the gold, gems, wildcards, vault progress and unopened boosters of the last inventory snapshot
+    Inventory {
+        /// Plot the history of a value per day instead
+        #[arg(long, value_parser = inventory::SERIES)]
+        plot: Option<String>,
+    },
+    /// Work with decklists
+    #[command(subcommand)]
+    Deck(DeckCommand),
+    /// Manage the configuration file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand)]
+enum DeckCommand {
+    /// Show the cards missing from the collection to build a deck, and whether the wildcards cover them
+    Cost {
+        /// The decklist, an MTGO `.dek`, a Forge `.dck`, or else in the Arena format, a card per line like
+        /// `4 Lightning Strike (M19) 152`
+        decklist_path: PathBuf,
+    },
+    /// Print a decklist in the Arena format with the printings of the card database, ready to be imported
+    Arena {
+        /// The decklist, an MTGO `.dek`, a Forge `.dck`, or else in the Arena format, the set and collector
+        /// number being optional
+        decklist_path: PathBuf,
+    },
+    /// Convert a decklist to another format, resolving its cards through the card database
+    Convert {
+        /// The decklist, printed in the other format
+        decklist_path: PathBuf,
+        /// The format of the decklist, guessed from the extension of the file by default
+        #[arg(long, value_parser = deck::FORMATS)]
+        from: Option<String>,
+        /// The format to print the decklist in
+        #[arg(long, value_parser = deck::FORMATS)]
+        to: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the configuration resolved from the defaults, the configuration file and the command line
+    Show,
+}
+
+/// The settings stored in the configuration file. Everything is optional: the command line overrides the
+/// file, and the file overrides the defaults.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub database_path: Option<PathBuf>,
+    pub collector_path: Option<PathBuf>,
+    pub player_log_path: Option<PathBuf>,
+    pub sets: Option<Vec<String>>,
+    pub sets_file: Option<PathBuf>,
+    pub all_sets: Option<bool>,
+    pub language: Option<String>,
+    pub account_id: Option<String>,
+}
+
+impl Settings {
+    fn load(path: &Path) -> Result<Settings, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Could not read the configuration file {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            format!(
+                "Could not parse the configuration file {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+    }
+
+    /// Fills in the defaults for the settings that are neither on the command line nor in the file.
+    fn resolve_defaults(&mut self) {
+        self.database_path
+            .get_or_insert_with(|| PathBuf::from(DEFAULT_DATABASE_PATH));
+        self.collector_path
+            .get_or_insert_with(|| PathBuf::from(DEFAULT_COLLECTOR_PATH));
+        if self.player_log_path.is_none() {
+            self.player_log_path = default_player_log_path();
+        }
+    }
+
+    fn set_filter(&self) -> Result<SetFilter, Box<dyn std::error::Error>> {
+        if self.all_sets.unwrap_or(false) {
+            Ok(SetFilter::AllArenaSets)
+        } else if let Some(sets) = &self.sets {
+            Ok(SetFilter::only(
+                sets.iter().flat_map(|sets| split_set_list(sets)),
+            ))
+        } else if let Some(sets_file) = &self.sets_file {
+            read_set_list(sets_file)
+        } else {
+            Ok(parse_set_list(DEFAULT_SETS))
+        }
+    }
+}
+
 pub struct Config {
     command: TrackerCommand,
+    config_path: Option<PathBuf>,
+    settings: Settings,
     database_path: PathBuf,
+    verbose: bool,
 }
 
 impl Config {
+    /// Parses the command line and the configuration file. Prints the help or the usage error and exits the
+    /// process if the arguments are not valid.
     pub fn new(args: Args) -> Result<Config, Box<dyn std::error::Error>> {
-        let default_collector_path = Path::new(
-            r"./mtga-datacollector/bin/x64/Release/netstandard2.1/mtga-datacollector.dll",
-        );
-        let default_database_path = Path::new(r"./mtga-tracker.db");
-        if args.len() == 1 {
-            Ok(Config {
-                command: TrackerCommand::Inject(default_collector_path.to_path_buf()),
-                database_path: default_database_path.to_path_buf(),
-            })
-        } else {
-            let args: Vec<String> = args.into_iter().collect();
-            match args[1].to_lowercase().as_str() {
-                "inject" => {
-                    let collector_path =
-                        args.get(2).map_or(default_collector_path, |p| Path::new(p));
-                    Ok(Config {
-                        command: TrackerCommand::Inject(collector_path.to_path_buf()),
-                        database_path: default_database_path.to_path_buf(),
-                    })
-                }
-                "createdb" => {
-                    let database_path =
-                        Path::new(args.get(2).ok_or("Please provide a database path")?);
-
-                    let required_sets = "MIR,WTH,MMQ,INV,PLS,ODY,TOR,JUD,ONS,LGN,SCG,8ED,MRD,DST,5DN,CHK,SOK,9ED,RAV,DIS,CSP,TSP,10E,LRW,MOR,SHM,ME2,ALA,ARB,M10,ZEN,WWK,ROE,M11,SOM,ME4,MBS,NPH,CMD,M12,ISD,DKA,AVR,CONF,M13,PLC,RTR,GTC,DGM,M14,THS,BNG,JOU,C13,MMA,M15,KTK,FRF,VMA,DTK,ORI,BFZ,OGW,SOI,EMN,AER,AKH,HOU,XLN,RIX,DAR,M19,ArenaSUP,G18,GRN,ANA,RNA,WAR,M20,ELD,THB,IKO,MH1,M21,JMP,UND,ZNR,C20,SLD,AKR,UST,KHM,ANB,KLR,MH2,STX,AFR,STA,CMR,2XM,MID,VOW,J21,NEO,Y22,SNC,HBG,DMU,C18,C21,CC2,NEC,UMA,WC".split(',').collect::<Vec<&str>>();
-
-                    Ok(Config {
-                        command: TrackerCommand::CreateDatabase(
-                            database_path.to_path_buf(),
-                            required_sets,
-                        ),
-                        database_path: default_database_path.to_path_buf(),
-                    })
-                }
-                _ => return Err("Unrecognized command".into()),
+        let cli = Cli::parse_from(args);
+
+        // an explicitly given configuration file has to exist, the default one is optional
+        let mut settings = match &cli.config_path {
+            Some(path) => Settings::load(path)?,
+            None => match default_config_path() {
+                Some(path) if path.exists() => Settings::load(&path)?,
+                _ => Settings::default(),
+            },
+        };
+        let config_path = cli.config_path.or_else(default_config_path);
+
+        if cli.database_path.is_some() {
+            settings.database_path = cli.database_path;
+        }
+        if cli.language.is_some() {
+            settings.language = cli.language;
+        }
+        if cli.account_id.is_some() {
+            settings.account_id = cli.account_id;
+        }
+        match &cli.command {
+            Some(CliCommand::Inject {
+                collector_path: Some(collector_path),
+            }) => settings.collector_path = Some(collector_path.clone()),
+            Some(CliCommand::CreateDatabase {
+                sets,
+                sets_file,
+                all_sets,
+                ..
+            }) if sets.is_some() || sets_file.is_some() || *all_sets => {
+                settings.sets = sets.clone();
+                settings.sets_file = sets_file.clone();
+                settings.all_sets = Some(*all_sets);
             }
+            Some(CliCommand::Ingest {
+                player_log_path: Some(player_log_path),
+                ..
+            }) => settings.player_log_path = Some(player_log_path.clone()),
+            _ => {}
         }
+        settings.resolve_defaults();
+
+        let command = match cli.command {
+            None | Some(CliCommand::Inject { .. }) => {
+                TrackerCommand::Inject(settings.collector_path.clone().unwrap_or_default())
+            }
+            Some(CliCommand::CreateDatabase {
+                scryfall_db_path,
+                arena_log,
+                update,
+                dry_run,
+                ..
+            }) => TrackerCommand::CreateDatabase {
+                scryfall_db_path,
+                set_filter: settings.set_filter()?,
+                arena_log_path: arena_log,
+                update,
+                dry_run,
+            },
+            Some(CliCommand::Ingest { follow, .. }) => {
+                let player_log_path = settings
+                    .player_log_path
+                    .clone()
+                    .ok_or("Could not find the user profile, please provide the Player.log path")?;
+                TrackerCommand::Ingest(player_log_path, follow)
+            }
+            Some(CliCommand::Collection { at }) => {
+                TrackerCommand::Collection(at.unwrap_or_else(Utc::now))
+            }
+            Some(CliCommand::Acquired { arena_id }) => TrackerCommand::Acquired(arena_id),
+            Some(CliCommand::Card { query, set, text }) => {
+                let query = query.join(" ");
+                TrackerCommand::Card(match (set, query.parse::<u32>()) {
+                    _ if text => CardQuery::Text(query),
+                    (Some(set), _) => CardQuery::SetNumber(set, query),
+                    (None, Ok(arena_id)) => CardQuery::ArenaId(arena_id),
+                    (None, Err(_)) => CardQuery::Name(query),
+                })
+            }
+            Some(CliCommand::Search { query }) => TrackerCommand::Search(query.join(" ").parse()?),
+            Some(CliCommand::Unmapped) => TrackerCommand::Unmapped(settings.set_filter()?),
+            Some(CliCommand::Bans { format }) => TrackerCommand::Bans(format.to_lowercase()),
+            Some(CliCommand::Prices {
+                currency,
+                since,
+                limit,
+            }) => TrackerCommand::Prices {
+                set_filter: settings.set_filter()?,
+                currency,
+                since: since.map(|since| since.naive_utc().date()),
+                limit,
+            },
+            Some(CliCommand::Value { currency, limit }) => TrackerCommand::Value(currency, limit),
+            Some(CliCommand::Completion { format }) => TrackerCommand::Completion(
+                settings.set_filter()?,
+                match format.as_str() {
+                    "json" => ReportFormat::Json,
+                    "csv" => ReportFormat::Csv,
+                    _ => ReportFormat::Table,
+                },
+            ),
+            Some(CliCommand::Inventory { plot }) => TrackerCommand::Inventory(plot),
+            Some(CliCommand::Deck(DeckCommand::Cost { decklist_path })) => {
+                TrackerCommand::DeckCost(decklist_path)
+            }
+            Some(CliCommand::Deck(DeckCommand::Arena { decklist_path })) => {
+                TrackerCommand::DeckArena(decklist_path)
+            }
+            Some(CliCommand::Deck(DeckCommand::Convert {
+                decklist_path,
+                from,
+                to,
+            })) => TrackerCommand::DeckConvert {
+                from: match from {
+                    Some(from) => from.parse()?,
+                    None => DeckFormat::from_path(&decklist_path),
+                },
+                to: to.parse()?,
+                decklist_path,
+            },
+            Some(CliCommand::Config(ConfigCommand::Show)) => TrackerCommand::ShowConfig,
+        };
+
+        Ok(Config {
+            command,
+            config_path,
+            database_path: settings.database_path.clone().unwrap_or_default(),
+            settings,
+            verbose: cli.verbose,
+        })
     }
 
     pub fn command(&self) -> &TrackerCommand {
@@ -56,9 +428,139 @@ impl Config {
     pub fn database_path(&self) -> &PathBuf {
         &self.database_path
     }
+
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    /// The preferred language of the card names and texts, if it is not English.
+    pub fn language(&self) -> Option<&str> {
+        self.settings
+            .language
+            .as_deref()
+            .filter(|language| *language != "en")
+    }
+
+    /// The Arena account whose history is shown, if one was chosen.
+    pub fn account_id(&self) -> Option<&str> {
+        self.settings.account_id.as_deref()
+    }
+
+    /// The configuration file the settings were read from, if the platform has a config directory.
+    pub fn config_path(&self) -> Option<&PathBuf> {
+        self.config_path.as_ref()
+    }
+
+    /// The resolved settings.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
 }
 
 pub enum TrackerCommand {
     Inject(PathBuf),
-    CreateDatabase(PathBuf, Vec<&'static str>),
+    CreateDatabase {
+        scryfall_db_path: PathBuf,
+        set_filter: SetFilter,
+        arena_log_path: Option<PathBuf>,
+        update: bool,
+        dry_run: bool,
+    },
+    Ingest(PathBuf, bool),
+    Collection(DateTime<Utc>),
+    Acquired(u32),
+    Card(CardQuery),
+    Search(Query),
+    Unmapped(SetFilter),
+    Bans(String),
+    Prices {
+        set_filter: SetFilter,
+        currency: String,
+        since: Option<NaiveDate>,
+        limit: usize,
+    },
+    Value(String, usize),
+    Completion(SetFilter, ReportFormat),
+    Inventory(Option<String>),
+    DeckCost(PathBuf),
+    DeckArena(PathBuf),
+    DeckConvert {
+        decklist_path: PathBuf,
+        from: DeckFormat,
+        to: DeckFormat,
+    },
+    ShowConfig,
+}
+
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub enum CardQuery {
+    ArenaId(u32),
+    Name(String),
+    SetNumber(String, String),
+    Text(String),
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("mtga-tracker").join("config.toml"))
+}
+
+/// Reads a set list file: set codes separated by commas or whitespace, `#` starts a comment.
+fn read_set_list<P>(path: P) -> Result<SetFilter, Box<dyn std::error::Error>>
+where
+    P: AsRef<Path>,
+{
+    let content = fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "Could not read the sets file {}: {}",
+            path.as_ref().display(),
+            e
+        )
+    })?;
+    Ok(parse_set_list(&content))
+}
+
+fn parse_set_list(content: &str) -> SetFilter {
+    let sets = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(split_set_list)
+        .collect::<Vec<_>>();
+    SetFilter::only(sets)
+}
+
+fn split_set_list(sets: &str) -> Vec<&str> {
+    sets.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|set| !set.is_empty())
+        .collect()
+}
+
+/// Accepts an RFC 3339 timestamp, or a plain `YYYY-MM-DD` date meaning the end of that day.
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let end_of_day = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Could not parse date: {}, please use YYYY-MM-DD", value))?
+        .and_hms_opt(23, 59, 59)
+        .ok_or("Invalid date")?;
+    Ok(Utc.from_utc_datetime(&end_of_day))
+}
+
+fn default_player_log_path() -> Option<PathBuf> {
+    let user_profile = std::env::var_os("USERPROFILE")?;
+
+    Some(
+        Path::new(&user_profile)
+            .join("AppData")
+            .join("LocalLow")
+            .join("Wizards Of The Coast")
+            .join("MTGA")
+            .join("Player.log"),
+    )
 }
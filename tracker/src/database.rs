@@ -0,0 +1,363 @@
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use rusqlite::Connection;
+
+/// The schema of the tracker database, as a list of migrations. The SQLite `user_version` of the database is
+/// the number of migrations already applied to it, so the migration at index `n` takes the database from
+/// version `n` to `n + 1`.
+///
+/// Applied migrations must never be changed, schema changes go into a new migration at the end of the list.
+const MIGRATIONS: &[&str] = &[
+    // 1: the card database. Databases written before the migrations existed already have it.
+    "CREATE TABLE IF NOT EXISTS cards_db (
+        'arena_id' INTEGER PRIMARY KEY NOT NULL,
+        'set' TEXT NOT NULL,
+        'rarity' TEXT NOT NULL,
+        'booster' INTEGER NOT NULL,
+        'data' BLOB NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS set_idx ON cards_db('set');
+    CREATE INDEX IF NOT EXISTS rarity_idx ON cards_db('rarity');",
+    // 2: collection snapshots and inventory updates
+    "CREATE TABLE IF NOT EXISTS collection_snapshots (
+        'id' INTEGER PRIMARY KEY NOT NULL,
+        'account_id' TEXT NOT NULL,
+        'first_seen' TEXT NOT NULL,
+        'last_seen' TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS collection_snapshots_idx
+        ON collection_snapshots('account_id', 'last_seen');
+
+    CREATE TABLE IF NOT EXISTS collection_cards (
+        'snapshot_id' INTEGER NOT NULL REFERENCES collection_snapshots('id'),
+        'arena_id' INTEGER NOT NULL,
+        'count' INTEGER NOT NULL,
+        PRIMARY KEY ('snapshot_id', 'arena_id')
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS collection_cards_arena_id_idx
+        ON collection_cards('arena_id');
+
+    CREATE TABLE IF NOT EXISTS inventory_updates (
+        'id' INTEGER PRIMARY KEY NOT NULL,
+        'account_id' TEXT NOT NULL,
+        'timestamp' TEXT NOT NULL,
+        'source' TEXT,
+        'gold_delta' INTEGER NOT NULL,
+        'gems_delta' INTEGER NOT NULL,
+        'wc_common_delta' INTEGER NOT NULL,
+        'wc_uncommon_delta' INTEGER NOT NULL,
+        'wc_rare_delta' INTEGER NOT NULL,
+        'wc_mythic_delta' INTEGER NOT NULL,
+        'vault_progress_delta' REAL NOT NULL,
+        'data' BLOB NOT NULL,
+        UNIQUE ('account_id', 'timestamp')
+    );
+
+    CREATE TABLE IF NOT EXISTS inventory_update_cards (
+        'update_id' INTEGER NOT NULL REFERENCES inventory_updates('id'),
+        'arena_id' INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS inventory_update_cards_arena_id_idx
+        ON inventory_update_cards('arena_id');",
+    // 3: the colored mana symbols in the mana cost of the cards, filled in by the next createdb
+    "ALTER TABLE cards_db ADD COLUMN 'pips_w' INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE cards_db ADD COLUMN 'pips_u' INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE cards_db ADD COLUMN 'pips_b' INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE cards_db ADD COLUMN 'pips_r' INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE cards_db ADD COLUMN 'pips_g' INTEGER NOT NULL DEFAULT 0;",
+    // 4: the cards in relational tables next to the data blob, filled in by the next createdb. Cards with a
+    // single face get one row in card_faces too, so the names and texts of all cards are in one table.
+    "CREATE TABLE IF NOT EXISTS card_faces (
+        'arena_id' INTEGER NOT NULL REFERENCES cards_db('arena_id'),
+        'face_index' INTEGER NOT NULL,
+        'name' TEXT NOT NULL,
+        'mana_cost' TEXT,
+        'type_line' TEXT,
+        'oracle_text' TEXT,
+        'power' TEXT,
+        'toughness' TEXT,
+        'loyalty' TEXT,
+        'flavor_text' TEXT,
+        PRIMARY KEY ('arena_id', 'face_index')
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS card_faces_name_idx ON card_faces('name');
+
+    CREATE TABLE IF NOT EXISTS card_colors (
+        'arena_id' INTEGER NOT NULL REFERENCES cards_db('arena_id'),
+        'kind' TEXT NOT NULL,
+        'color' TEXT NOT NULL,
+        PRIMARY KEY ('arena_id', 'kind', 'color')
+    ) WITHOUT ROWID;
+
+    CREATE TABLE IF NOT EXISTS card_keywords (
+        'arena_id' INTEGER NOT NULL REFERENCES cards_db('arena_id'),
+        'keyword' TEXT NOT NULL,
+        PRIMARY KEY ('arena_id', 'keyword')
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS card_keywords_keyword_idx ON card_keywords('keyword');
+
+    CREATE TABLE IF NOT EXISTS card_legalities (
+        'arena_id' INTEGER NOT NULL REFERENCES cards_db('arena_id'),
+        'format' TEXT NOT NULL,
+        'legality' TEXT NOT NULL,
+        PRIMARY KEY ('arena_id', 'format')
+    ) WITHOUT ROWID;
+    CREATE INDEX IF NOT EXISTS card_legalities_format_idx ON card_legalities('format', 'legality');
+
+    CREATE TABLE IF NOT EXISTS card_parts (
+        'arena_id' INTEGER NOT NULL REFERENCES cards_db('arena_id'),
+        'scryfall_id' TEXT NOT NULL,
+        'component' TEXT NOT NULL,
+        'name' TEXT NOT NULL,
+        'type_line' TEXT NOT NULL,
+        PRIMARY KEY ('arena_id', 'scryfall_id')
+    ) WITHOUT ROWID;",
+    // 5: full-text index over the texts of the card faces, filled in by the next createdb
+    "CREATE VIRTUAL TABLE IF NOT EXISTS cards_fts USING fts5(
+        arena_id UNINDEXED,
+        face_index UNINDEXED,
+        name,
+        type_line,
+        oracle_text,
+        flavor_text,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );",
+    // 6: the cards of the client's card database that have no Scryfall card
+    "CREATE TABLE IF NOT EXISTS arena_only_cards (
+        'arena_id' INTEGER PRIMARY KEY NOT NULL,
+        'title' TEXT,
+        'set' TEXT NOT NULL,
+        'rarity' TEXT NOT NULL,
+        'collector_number' TEXT NOT NULL,
+        'digital_only' INTEGER NOT NULL,
+        'rebalanced' INTEGER NOT NULL,
+        'data' BLOB NOT NULL
+    );",
+    // 7: the whole client card database, to tell why a card of the collection is not in the card database
+    "CREATE TABLE IF NOT EXISTS client_cards (
+        'arena_id' INTEGER PRIMARY KEY NOT NULL,
+        'title' TEXT,
+        'set' TEXT NOT NULL,
+        'data' BLOB NOT NULL
+    );",
+    // 8: the legality changes seen when the card database is refreshed, the card tables only keep the current ones
+    "CREATE TABLE IF NOT EXISTS legality_history (
+        'arena_id' INTEGER NOT NULL,
+        'name' TEXT NOT NULL,
+        'format' TEXT NOT NULL,
+        'old_legality' TEXT,
+        'new_legality' TEXT,
+        'timestamp' TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS legality_history_format_idx ON legality_history('format', 'timestamp');",
+    // 9: the prices of the cards on the days the card database was built, a missing price is stored as NULL
+    "CREATE TABLE IF NOT EXISTS price_history (
+        'arena_id' INTEGER NOT NULL,
+        'date' TEXT NOT NULL,
+        'currency' TEXT NOT NULL,
+        'price' REAL,
+        PRIMARY KEY ('date', 'currency', 'arena_id')
+    ) WITHOUT ROWID;",
+    // 10: the texts of the non-English printings, the cards themselves are English
+    "CREATE TABLE IF NOT EXISTS card_localizations (
+        'arena_id' INTEGER NOT NULL,
+        'lang' TEXT NOT NULL,
+        'printed_name' TEXT,
+        'printed_text' TEXT,
+        'printed_type_line' TEXT,
+        PRIMARY KEY ('arena_id', 'lang')
+    ) WITHOUT ROWID;",
+    // 11: inventory snapshots, deduplicated like the collection snapshots
+    "CREATE TABLE IF NOT EXISTS inventory_snapshots (
+        'id' INTEGER PRIMARY KEY NOT NULL,
+        'account_id' TEXT NOT NULL,
+        'first_seen' TEXT NOT NULL,
+        'last_seen' TEXT NOT NULL,
+        'gold' INTEGER NOT NULL,
+        'gems' INTEGER NOT NULL,
+        'wc_common' INTEGER NOT NULL,
+        'wc_uncommon' INTEGER NOT NULL,
+        'wc_rare' INTEGER NOT NULL,
+        'wc_mythic' INTEGER NOT NULL,
+        'vault_progress' REAL NOT NULL,
+        'data' BLOB NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS inventory_snapshots_idx
+        ON inventory_snapshots('account_id', 'last_seen');",
+    // 12: the full-text index keyed by the rowid `arena_id * 16 + face_index`, as the UNINDEXED arena_id
+    // column cannot be searched without scanning the index, refilled from card_faces
+    "DROP TABLE IF EXISTS cards_fts;
+    CREATE VIRTUAL TABLE cards_fts USING fts5(
+        arena_id UNINDEXED,
+        face_index UNINDEXED,
+        name,
+        type_line,
+        oracle_text,
+        flavor_text,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    INSERT INTO cards_fts (rowid, arena_id, face_index, name, type_line, oracle_text, flavor_text)
+        SELECT arena_id * 16 + face_index, arena_id, face_index, name, type_line, oracle_text, flavor_text
+        FROM card_faces;",
+];
+
+/// Timestamps are stored as UTC RFC 3339 strings, so they sort chronologically as text.
+pub fn timestamp<Tz>(timestamp: &DateTime<Tz>) -> String
+where
+    Tz: TimeZone,
+{
+    timestamp
+        .with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Opens the tracker database, creating it if needed, and brings its schema up to date.
+pub fn open<P>(path: P) -> Result<Connection, Box<dyn Error>>
+where
+    P: AsRef<Path>,
+{
+    let mut db = Connection::open(path)?;
+    migrate(&mut db)?;
+    Ok(db)
+}
+
+fn migrate(db: &mut Connection) -> Result<(), Box<dyn Error>> {
+    let version: usize = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "The database has schema version {}, but this tracker only knows version {}",
+            version,
+            MIGRATIONS.len()
+        )
+        .into());
+    }
+
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = db.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", idx + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempFile;
+
+    fn user_version(db: &Connection) -> usize {
+        db.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn creates_the_latest_schema() {
+        let mut db = Connection::open_in_memory().unwrap();
+        migrate(&mut db).unwrap();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+
+        migrate(&mut db).unwrap();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let mut db = Connection::open_in_memory().unwrap();
+        db.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(migrate(&mut db).is_err());
+    }
+
+    #[test]
+    fn migrates_a_card_database_written_before_the_migrations() {
+        let file = TempFile::new("legacy.db");
+        let legacy = Connection::open(&file.0).unwrap();
+        legacy
+            .execute_batch(
+                "CREATE TABLE cards_db (
+                    'arena_id' INTEGER PRIMARY KEY NOT NULL,
+                    'set' TEXT NOT NULL,
+                    'rarity' TEXT NOT NULL,
+                    'booster' INTEGER NOT NULL,
+                    'data' BLOB NOT NULL
+                );
+                CREATE INDEX set_idx ON cards_db('set');
+                CREATE INDEX rarity_idx ON cards_db('rarity');
+                INSERT INTO cards_db ('arena_id', 'set', 'rarity', 'booster', 'data')
+                    VALUES (70002, 'm19', 'common', 1, '{\"name\":\"Lightning Strike\"}');",
+            )
+            .unwrap();
+        drop(legacy);
+
+        let db = open(&file.0).unwrap();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+        let card: (u32, String, String, bool, String, u32) = db
+            .query_row(
+                "SELECT arena_id, \"set\", rarity, booster, data, pips_r FROM cards_db",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            card,
+            (
+                70002,
+                "m19".to_string(),
+                "common".to_string(),
+                true,
+                "{\"name\":\"Lightning Strike\"}".to_string(),
+                0
+            )
+        );
+    }
+
+    #[test]
+    fn rebuilds_the_full_text_index_keyed_by_rowid() {
+        let mut db = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..11] {
+            db.execute_batch(migration).unwrap();
+        }
+        db.pragma_update(None, "user_version", 11).unwrap();
+        db.execute_batch(
+            "INSERT INTO cards_db ('arena_id', 'set', 'rarity', 'booster', 'data')
+                VALUES (70004, 'eld', 'mythic', 1, '{}');
+            INSERT INTO card_faces ('arena_id', 'face_index', 'name') VALUES
+                (70004, 0, 'Brazen Borrower'),
+                (70004, 1, 'Petty Theft');
+            INSERT INTO cards_fts (arena_id, face_index, name) VALUES
+                (70004, 0, 'Brazen Borrower'),
+                (70010, 0, 'Removed Card');",
+        )
+        .unwrap();
+
+        migrate(&mut db).unwrap();
+        let mut statement = db
+            .prepare("SELECT rowid, arena_id, name FROM cards_fts ORDER BY rowid")
+            .unwrap();
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<Vec<(i64, u32, String)>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (70004 * 16, 70004, "Brazen Borrower".to_string()),
+                (70004 * 16 + 1, 70004, "Petty Theft".to_string())
+            ]
+        );
+    }
+}
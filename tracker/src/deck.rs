@@ -0,0 +1,784 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+mod arena;
+mod dek;
+mod forge;
+mod text;
+
+use crate::collector::model::{Collection, Inventory};
+use crate::mtgadb::model::{Layout, Rarity, ScryCard};
+use crate::mtgadb::MtgaDb;
+
+/// The number of copies of a card a constructed deck can have.
+const PLAYSET: u32 = 4;
+
+/// The names of the decklist formats, as the command line takes them.
+pub const FORMATS: [&str; 4] = ["arena", "dek", "dck", "text"];
+
+/// A constructed deck: the commander and the companion, if the deck has them, the main deck and the sideboard.
+#[derive(Debug, Default, PartialEq)]
+pub struct Deck {
+    pub name: Option<String>,
+    pub commander: Vec<DeckEntry>,
+    pub companion: Vec<DeckEntry>,
+    pub main: Vec<DeckEntry>,
+    pub sideboard: Vec<DeckEntry>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Section {
+    Commander,
+    Companion,
+    Main,
+    Sideboard,
+}
+
+/// A line of a decklist: a number of copies of a card, optionally of a given printing. The Arena id is only
+/// known once the entry is resolved through the card database, the MTGO id also comes from MTGO decklists.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeckEntry {
+    pub count: u32,
+    pub name: String,
+    /// The Scryfall code of the set, in lowercase. The formats with other set codes convert them when
+    /// reading and writing decklists.
+    pub set: Option<String>,
+    pub collector_number: Option<String>,
+    pub arena_id: Option<u32>,
+    pub mtgo_id: Option<u32>,
+}
+
+/// The decklist formats the tracker reads and writes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeckFormat {
+    /// The MTG Arena clipboard format.
+    Arena,
+    /// The XML `.dek` files of MTGO.
+    Dek,
+    /// The `.dck` files of Forge.
+    Dck,
+    /// A card per line, like `4 Lightning Strike`, and the sideboard after an empty line.
+    Text,
+}
+
+/// A problem with a line of a decklist.
+#[derive(Debug)]
+pub enum DeckError {
+    /// A line that is neither a card nor a section header, with its line number.
+    Syntax { line: usize, text: String },
+    /// A card the card database has no printing of, neither by the set and collector number nor by name.
+    UnknownCard { section: Section, entry: DeckEntry },
+}
+
+/// Every problem found in a decklist, so they can be fixed at once.
+#[derive(Debug)]
+pub struct DeckErrors(pub Vec<DeckError>);
+
+/// The missing copies of a card, and the printing the wildcards are counted for.
+pub struct MissingCard<'a> {
+    pub card: &'a ScryCard,
+    pub count: u32,
+}
+
+/// A number of wildcards of each rarity.
+#[derive(Debug, Default, PartialEq)]
+pub struct Wildcards {
+    pub common: u32,
+    pub uncommon: u32,
+    pub rare: u32,
+    pub mythic: u32,
+    /// The cards of the special and bonus rarities, which have no wildcard of their own.
+    pub other: u32,
+}
+
+/// What is needed to build a deck from a collection.
+pub struct CraftingCost<'a> {
+    pub missing: Vec<MissingCard<'a>>,
+    pub wildcards: Wildcards,
+}
+
+impl Deck {
+    pub fn section(&self, section: Section) -> &Vec<DeckEntry> {
+        match section {
+            Section::Commander => &self.commander,
+            Section::Companion => &self.companion,
+            Section::Main => &self.main,
+            Section::Sideboard => &self.sideboard,
+        }
+    }
+
+    pub fn section_mut(&mut self, section: Section) -> &mut Vec<DeckEntry> {
+        match section {
+            Section::Commander => &mut self.commander,
+            Section::Companion => &mut self.companion,
+            Section::Main => &mut self.main,
+            Section::Sideboard => &mut self.sideboard,
+        }
+    }
+
+    /// The sideboard with the companion in it, unless it is there already, for the formats that list the
+    /// companion in the sideboard.
+    fn sideboard_with_companion(&self) -> Vec<&DeckEntry> {
+        let mut sideboard = self
+            .companion
+            .iter()
+            .filter(|companion| {
+                !self
+                    .sideboard
+                    .iter()
+                    .any(|entry| entry.name.eq_ignore_ascii_case(&companion.name))
+            })
+            .collect::<Vec<_>>();
+        sideboard.extend(&self.sideboard);
+        sideboard
+    }
+
+    /// Takes the copies of the companion out of the sideboard, where Arena lists it as well, so it is only
+    /// counted once.
+    fn take_companion_from_sideboard(&mut self) {
+        for companion in &self.companion {
+            let mut count = companion.count;
+            for entry in &mut self.sideboard {
+                if entry.name.eq_ignore_ascii_case(&companion.name) {
+                    let taken = entry.count.min(count);
+                    entry.count -= taken;
+                    count -= taken;
+                }
+            }
+        }
+        self.sideboard.retain(|entry| entry.count > 0);
+    }
+
+    /// Resolves every entry to a printing of the card database, replacing the name, set and collector number
+    /// with the ones of the printing, the name as Arena writes it. The MTGO id is the one of the printing, or of
+    /// another printing of the card if MTGO does not have this one. Fails with every entry that could not be
+    /// resolved.
+    pub fn resolve(&self, database: &MtgaDb) -> Result<Deck, DeckErrors> {
+        let mut resolved = Deck {
+            name: self.name.clone(),
+            ..Deck::default()
+        };
+        let mut errors = Vec::new();
+        for section in Section::ALL {
+            for entry in self.section(section) {
+                match entry.resolve(database) {
+                    Some(card) => resolved.section_mut(section).push(DeckEntry {
+                        count: entry.count,
+                        name: arena_name(card).to_string(),
+                        set: Some(card.set.clone()),
+                        collector_number: Some(card.collector_number.clone()),
+                        arena_id: card.arena_id,
+                        mtgo_id: card.mtgo_id.or_else(|| {
+                            database
+                                .find_by_exact_name(&card.name)
+                                .into_iter()
+                                .find_map(|printing| printing.mtgo_id)
+                        }),
+                    }),
+                    None => errors.push(DeckError::UnknownCard {
+                        section,
+                        entry: entry.clone(),
+                    }),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(resolved)
+        } else {
+            Err(DeckErrors(errors))
+        }
+    }
+
+    /// Counts the cards missing from the collection to build the deck with its sideboard, and the wildcards
+    /// needed to craft them.
+    ///
+    /// A card is owned in any of its printings, so the copies are counted per Oracle card. The printings of
+    /// the sets left out of the card database are only counted if the client's card database names them. A
+    /// missing card is crafted in the printing the decklist names, or in its printing of the lowest rarity
+    /// that has a wildcard. Basic lands are free, and four copies of a card a deck can have any number of are
+    /// enough, as on Arena. Fails with every entry that could not be resolved.
+    pub fn crafting_cost<'a>(
+        &self,
+        database: &'a MtgaDb,
+        collection: &Collection,
+    ) -> Result<CraftingCost<'a>, DeckErrors> {
+        let mut owned = HashMap::new();
+        for (arena_id, count) in collection.iter() {
+            let card = database.card(arena_id).or_else(|| {
+                let title = database.client_cards().get(&arena_id)?.title.as_deref()?;
+                database.find_by_exact_name(title).into_iter().next()
+            });
+            if let Some(card) = card {
+                *owned.entry(oracle_key(card)).or_insert(0) += count;
+            }
+        }
+
+        // the cards of the deck in the order of the decklist, with the copies needed of each
+        let mut needed: Vec<(&ScryCard, u32)> = Vec::new();
+        let mut errors = Vec::new();
+        for section in Section::ALL {
+            for entry in self.section(section) {
+                let card = match entry.resolve(database) {
+                    Some(card) => card,
+                    None => {
+                        errors.push(DeckError::UnknownCard {
+                            section,
+                            entry: entry.clone(),
+                        });
+                        continue;
+                    }
+                };
+                match needed
+                    .iter_mut()
+                    .find(|(needed_card, _)| oracle_key(needed_card) == oracle_key(card))
+                {
+                    Some((_, count)) => *count += entry.count,
+                    None => needed.push((card, entry.count)),
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(DeckErrors(errors));
+        }
+
+        let mut cost = CraftingCost {
+            missing: Vec::new(),
+            wildcards: Wildcards::default(),
+        };
+        for (card, mut count) in needed {
+            if is_basic_land(card) {
+                continue;
+            }
+            if has_any_number_rule(card) {
+                count = count.min(PLAYSET);
+            }
+            let missing = count.saturating_sub(owned.get(&oracle_key(card)).copied().unwrap_or(0));
+            if missing == 0 {
+                continue;
+            }
+
+            match card.rarity {
+                Rarity::Common => cost.wildcards.common += missing,
+                Rarity::Uncommon => cost.wildcards.uncommon += missing,
+                Rarity::Rare => cost.wildcards.rare += missing,
+                Rarity::Mythic => cost.wildcards.mythic += missing,
+                _ => cost.wildcards.other += missing,
+            }
+            cost.missing.push(MissingCard {
+                card,
+                count: missing,
+            });
+        }
+        Ok(cost)
+    }
+}
+
+impl Section {
+    /// The sections in the order Arena writes them.
+    pub const ALL: [Section; 4] = [
+        Section::Commander,
+        Section::Companion,
+        Section::Main,
+        Section::Sideboard,
+    ];
+}
+
+impl DeckEntry {
+    /// An entry of a card by name only.
+    fn new(count: u32, name: &str) -> DeckEntry {
+        DeckEntry {
+            count,
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            arena_id: None,
+            mtgo_id: None,
+        }
+    }
+
+    /// Finds the printing of the entry by its MTGO id or its set and collector number, as long as it has the
+    /// name of the entry. Otherwise, or if the entry names no printing, finds the printing that is the
+    /// cheapest to craft by name, in the set of the entry if it has one there.
+    pub fn resolve<'a>(&self, database: &'a MtgaDb) -> Option<&'a ScryCard> {
+        let printing = self
+            .mtgo_id
+            .and_then(|mtgo_id| database.find_by_mtgo_id(mtgo_id))
+            .or_else(|| match (&self.set, &self.collector_number) {
+                (Some(set), Some(collector_number)) => {
+                    database.find_by_set_number(set, collector_number)
+                }
+                _ => None,
+            });
+
+        let printings = database.find_by_exact_name(&self.name);
+        if printings.is_empty() {
+            return printing;
+        }
+        match printing {
+            Some(printing) if printings.iter().any(|card| card.id == printing.id) => Some(printing),
+            _ => {
+                let in_set = printings
+                    .iter()
+                    .copied()
+                    .filter(|card| {
+                        self.set
+                            .as_ref()
+                            .is_some_and(|set| card.set.eq_ignore_ascii_case(set))
+                    })
+                    .collect::<Vec<_>>();
+                let printings = if in_set.is_empty() { printings } else { in_set };
+                printings
+                    .into_iter()
+                    .min_by_key(|card| crafting_rank(&card.rarity))
+            }
+        }
+    }
+}
+
+impl DeckFormat {
+    /// Guesses the format of a decklist from the extension of the file, the Arena format if it is not a
+    /// `.dek` or a `.dck`.
+    pub fn from_path(path: &Path) -> DeckFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("dek") => DeckFormat::Dek,
+            Some(extension) if extension.eq_ignore_ascii_case("dck") => DeckFormat::Dck,
+            _ => DeckFormat::Arena,
+        }
+    }
+
+    pub fn parse(self, decklist: &str) -> Result<Deck, DeckErrors> {
+        match self {
+            DeckFormat::Arena => arena::parse(decklist),
+            DeckFormat::Dek => dek::parse(decklist),
+            DeckFormat::Dck => forge::parse(decklist),
+            DeckFormat::Text => text::parse(decklist),
+        }
+    }
+
+    pub fn serialize(self, deck: &Deck) -> String {
+        match self {
+            DeckFormat::Arena => arena::serialize(deck),
+            DeckFormat::Dek => dek::serialize(deck),
+            DeckFormat::Dck => forge::serialize(deck),
+            DeckFormat::Text => text::serialize(deck),
+        }
+    }
+}
+
+impl FromStr for DeckFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(format: &str) -> Result<DeckFormat, Self::Err> {
+        match format {
+            "arena" => Ok(DeckFormat::Arena),
+            "dek" => Ok(DeckFormat::Dek),
+            "dck" => Ok(DeckFormat::Dck),
+            "text" => Ok(DeckFormat::Text),
+            _ => Err(format!("Unknown deck format: {}", format).into()),
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Commander => write!(f, "Commander"),
+            Section::Companion => write!(f, "Companion"),
+            Section::Main => write!(f, "Deck"),
+            Section::Sideboard => write!(f, "Sideboard"),
+        }
+    }
+}
+
+impl fmt::Display for DeckEntry {
+    /// Writes the entry as an Arena decklist line, like `4 Lightning Strike (M19) 152`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.count, self.name)?;
+        if let Some(set) = &self.set {
+            write!(f, " ({})", arena::set_code(set))?;
+            if let Some(collector_number) = &self.collector_number {
+                write!(f, " {}", collector_number)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeckError::Syntax { line, text } => write!(f, "Line {}: not a card: {}", line, text),
+            DeckError::UnknownCard { section, entry } => {
+                write!(f, "{}: unknown card: {}", section, entry)
+            }
+        }
+    }
+}
+
+impl Error for DeckError {}
+
+impl fmt::Display for DeckErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self.0.iter().map(|error| error.to_string());
+        write!(f, "{}", errors.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+impl Error for DeckErrors {}
+
+impl Wildcards {
+    /// The wildcards of an inventory.
+    pub fn owned(inventory: &Inventory) -> Wildcards {
+        Wildcards {
+            common: inventory.wc_common.max(0) as u32,
+            uncommon: inventory.wc_uncommon.max(0) as u32,
+            rare: inventory.wc_rare.max(0) as u32,
+            mythic: inventory.wc_mythic.max(0) as u32,
+            other: 0,
+        }
+    }
+
+    /// The wildcards missing from these to pay for the others. Only the four wildcard rarities are compared,
+    /// no wildcard crafts the other cards.
+    pub fn shortage(&self, cost: &Wildcards) -> Wildcards {
+        Wildcards {
+            common: cost.common.saturating_sub(self.common),
+            uncommon: cost.uncommon.saturating_sub(self.uncommon),
+            rare: cost.rare.saturating_sub(self.rare),
+            mythic: cost.mythic.saturating_sub(self.mythic),
+            other: 0,
+        }
+    }
+}
+
+impl fmt::Display for Wildcards {
+    /// Writes the four wildcard rarities, the other cards have no wildcard.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} common, {} uncommon, {} rare, {} mythic",
+            self.common, self.uncommon, self.rare, self.mythic
+        )
+    }
+}
+
+/// Prints the cards missing to build a deck and the wildcards needed to craft them, and whether the owned
+/// wildcards cover them, if the inventory is known.
+pub fn print_cost(cost: &CraftingCost, owned: Option<&Wildcards>) {
+    if cost.missing.is_empty() {
+        println!("Every card of the deck is in the collection.");
+        return;
+    }
+    println!("Missing cards:");
+    for missing in &cost.missing {
+        println!(
+            "{:>3} {} ({} #{}, {})",
+            missing.count,
+            missing.card.name,
+            missing.card.set.to_uppercase(),
+            missing.card.collector_number,
+            missing.card.rarity
+        );
+    }
+    println!("Wildcards needed: {}", cost.wildcards);
+
+    let owned = match owned {
+        Some(owned) => owned,
+        None => {
+            println!("No inventory snapshot was stored yet, the wildcards cannot be checked.");
+            return;
+        }
+    };
+    println!("Wildcards owned:  {}", owned);
+    let shortage = owned.shortage(&cost.wildcards);
+    if shortage == Wildcards::default() {
+        println!("The wildcards cover the deck.");
+    } else {
+        println!("Wildcards short: {}", shortage);
+    }
+    if cost.wildcards.other > 0 {
+        println!(
+            "{} of the missing cards have no wildcard rarity and cannot be crafted.",
+            cost.wildcards.other
+        );
+    }
+}
+
+/// Identifies the card across its printings. Only a few cards, like the reversible ones, have no Oracle id
+/// and are identified by their name.
+fn oracle_key(card: &ScryCard) -> String {
+    match &card.oracle_id {
+        Some(oracle_id) => oracle_id.to_string(),
+        None => card.name.clone(),
+    }
+}
+
+/// Orders the rarities by the wildcard that crafts them, the rarities without a wildcard last.
+fn crafting_rank(rarity: &Rarity) -> u8 {
+    match rarity {
+        Rarity::Common => 0,
+        Rarity::Uncommon => 1,
+        Rarity::Rare => 2,
+        Rarity::Mythic => 3,
+        _ => 4,
+    }
+}
+
+fn is_basic_land(card: &ScryCard) -> bool {
+    card.type_line
+        .as_deref()
+        .is_some_and(|type_line| type_line.starts_with("Basic Land"))
+}
+
+fn has_any_number_rule(card: &ScryCard) -> bool {
+    card.oracle_text
+        .as_deref()
+        .is_some_and(|text| text.contains("A deck can have any number of cards named"))
+}
+
+/// The name Arena uses for the card: the full name of split cards, the name of the front face of the other
+/// multi-face cards.
+fn arena_name(card: &ScryCard) -> &str {
+    match (&card.layout, &card.card_faces) {
+        (Layout::Split, _) => &card.name,
+        (_, Some(faces)) if !faces.is_empty() => &faces[0].name,
+        _ => &card.name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::collector::model::ArenaCard;
+    use crate::mtgadb::SetFilter;
+    use crate::testing;
+
+    fn database() -> MtgaDb {
+        MtgaDb::create_from_scryfall_db(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/scryfall.json"),
+            &SetFilter::AllArenaSets,
+            Vec::new(),
+            |_| {},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_the_entries_to_printings() {
+        let deck = DeckFormat::Arena
+            .parse("4 lightning strike\n2 Brazen Borrower // Petty Theft\n")
+            .unwrap()
+            .resolve(&database())
+            .unwrap();
+
+        assert_eq!(deck.main[0].to_string(), "4 Lightning Strike (M19) 152");
+        assert_eq!(deck.main[0].arena_id, Some(70002));
+        assert_eq!(deck.main[0].mtgo_id, Some(68212));
+        // Arena writes the name of the front face of an adventure
+        assert_eq!(deck.main[1].name, "Brazen Borrower");
+        assert_eq!(deck.main[1].arena_id, Some(70004));
+    }
+
+    #[test]
+    fn fails_with_every_unknown_card() {
+        let deck = DeckFormat::Arena
+            .parse("Deck\n4 Lightning Strike\n4 Opt\n\nSideboard\n2 Negate (M19) 65\n")
+            .unwrap();
+        let errors = deck.resolve(&database()).unwrap_err();
+
+        let unknown = errors
+            .0
+            .iter()
+            .map(|error| match error {
+                DeckError::UnknownCard { section, entry } => (*section, entry.name.as_str()),
+                other => panic!("unexpected error {}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unknown,
+            [(Section::Main, "Opt"), (Section::Sideboard, "Negate")]
+        );
+        assert_eq!(
+            errors.to_string(),
+            "Deck: unknown card: 4 Opt\nSideboard: unknown card: 2 Negate (M19) 65"
+        );
+    }
+
+    #[test]
+    fn converts_the_companion_without_doubling_it() {
+        let deck = DeckFormat::Arena
+            .parse(
+                "Companion\n1 Lurrus of the Dream-Den\n\nDeck\n4 Opt\n\nSideboard\n1 Lurrus of the Dream-Den\n",
+            )
+            .unwrap();
+        assert_eq!(
+            Section::ALL
+                .iter()
+                .flat_map(|section| deck.section(*section))
+                .map(|entry| entry.count)
+                .sum::<u32>(),
+            5
+        );
+
+        for format in [
+            DeckFormat::Arena,
+            DeckFormat::Dek,
+            DeckFormat::Dck,
+            DeckFormat::Text,
+        ] {
+            let converted = format.parse(&format.serialize(&deck)).unwrap();
+            let copies = Section::ALL
+                .iter()
+                .flat_map(|section| converted.section(*section))
+                .filter(|entry| entry.name == "Lurrus of the Dream-Den")
+                .map(|entry| entry.count)
+                .sum::<u32>();
+            assert_eq!(copies, 1, "{:?}", format);
+        }
+    }
+
+    /// A card with an Oracle id of its own, unless the fields give it another one.
+    fn card(arena_id: u32, name: &str, rarity: &str, fields: serde_json::Value) -> ScryCard {
+        let mut card = json!({
+            "arena_id": arena_id,
+            "id": format!("00000000-0000-0000-0000-{:012}", arena_id),
+            "oracle_id": format!("00000000-0000-0000-0001-{:012}", arena_id),
+            "name": name,
+            "rarity": rarity,
+            "collector_number": arena_id.to_string(),
+            "mtgo_id": null,
+        });
+        for (field, value) in fields.as_object().unwrap() {
+            card[field] = value.clone();
+        }
+        testing::card(card)
+    }
+
+    fn crafting_database() -> MtgaDb {
+        MtgaDb::from_cards(
+            vec![
+                card(1, "Opt", "common", json!({})),
+                card(2, "Negate", "common", json!({})),
+                card(
+                    3,
+                    "Negate",
+                    "uncommon",
+                    json!({"oracle_id": "00000000-0000-0000-0001-000000000002", "set": "dom"}),
+                ),
+                card(4, "Shock", "uncommon", json!({})),
+                card(5, "Glorybringer", "mythic", json!({})),
+                card(6, "Sheoldred", "rare", json!({})),
+                card(
+                    7,
+                    "Island",
+                    "common",
+                    json!({"type_line": "Basic Land \u{2014} Island"}),
+                ),
+                card(
+                    8,
+                    "Rat Colony",
+                    "common",
+                    json!({
+                        "oracle_text": "A deck can have any number of cards named Rat Colony.",
+                    }),
+                ),
+                card(9, "Mystical Archive Card", "special", json!({})),
+            ],
+            vec![ArenaCard {
+                grp_id: 100,
+                title: Some("Shock".to_string()),
+                set: "M20".to_string(),
+                ..ArenaCard::default()
+            }],
+        )
+    }
+
+    fn cost(decklist: &str, collection: &[(u32, u32)]) -> Wildcards {
+        let database = crafting_database();
+        let deck = DeckFormat::Text.parse(decklist).unwrap();
+        let collection = collection.iter().copied().collect::<Collection>();
+        deck.crafting_cost(&database, &collection)
+            .unwrap()
+            .wildcards
+    }
+
+    #[test]
+    fn counts_the_wildcards_per_rarity() {
+        assert_eq!(
+            cost(
+                "4 Opt\n3 Shock\n2 Glorybringer\n1 Sheoldred\n\n1 Mystical Archive Card\n",
+                &[]
+            ),
+            Wildcards {
+                common: 4,
+                uncommon: 3,
+                rare: 1,
+                mythic: 2,
+                other: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn leaves_out_the_basic_lands() {
+        assert_eq!(cost("20 Island\n", &[]), Wildcards::default());
+    }
+
+    #[test]
+    fn counts_the_owned_copies_across_printings() {
+        // the decklist and the collection name different printings of Negate
+        assert_eq!(
+            cost("4 Negate (DOM)\n", &[(2, 3)]),
+            Wildcards {
+                uncommon: 1,
+                ..Wildcards::default()
+            }
+        );
+        // the copies in the main deck and the sideboard add up
+        assert_eq!(
+            cost("3 Opt\n\n2 Opt\n", &[(1, 4)]),
+            Wildcards {
+                common: 1,
+                ..Wildcards::default()
+            }
+        );
+    }
+
+    #[test]
+    fn counts_the_owned_printings_of_left_out_sets_by_name() {
+        assert_eq!(cost("4 Shock\n", &[(100, 4)]), Wildcards::default());
+    }
+
+    #[test]
+    fn needs_only_a_playset_of_the_cards_a_deck_can_have_any_number_of() {
+        assert_eq!(
+            cost("30 Rat Colony\n", &[(8, 1)]),
+            Wildcards {
+                common: 3,
+                ..Wildcards::default()
+            }
+        );
+        assert_eq!(cost("30 Rat Colony\n", &[(8, 4)]), Wildcards::default());
+    }
+
+    #[test]
+    fn fails_the_crafting_cost_with_every_unknown_card() {
+        let deck = DeckFormat::Text
+            .parse("4 Opt\n4 Lightning Bolt\n\n2 Duress\n")
+            .unwrap();
+        let errors = deck
+            .crafting_cost(&crafting_database(), &Collection::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors.to_string(),
+            "Deck: unknown card: 4 Lightning Bolt\nSideboard: unknown card: 2 Duress"
+        );
+    }
+}
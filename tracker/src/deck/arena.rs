@@ -0,0 +1,224 @@
+use super::{Deck, DeckEntry, DeckError, DeckErrors, Section};
+
+/// The sets Arena has another code for than Scryfall, as Arena and Scryfall code pairs.
+const SET_CODES: [(&str, &str); 1] = [("DAR", "dom")];
+
+/// Parses a decklist in the format of the MTG Arena clipboard:
+///
+/// ```text
+/// Companion
+/// 1 Lurrus of the Dream-Den (IKO) 226
+///
+/// Deck
+/// 4 Lightning Strike (M19) 152
+/// 20 Mountain (DMU) 269
+///
+/// Sideboard
+/// 2 Brazen Borrower (ELD) 39
+/// ```
+///
+/// The set and collector number are optional, and older exports leave out the headers, putting the sideboard
+/// after an empty line. Lines starting with `//` are skipped, and the `About` section only gives the name of
+/// the deck. The companion is listed in the sideboard as well, that copy is left out of the sideboard. Fails
+/// with every line that is not a card or a section header.
+pub fn parse(decklist: &str) -> Result<Deck, DeckErrors> {
+    let mut deck = Deck::default();
+    let mut errors = Vec::new();
+    // None in the About section
+    let mut section = Some(Section::Main);
+    for (index, line) in decklist.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            if section == Some(Section::Main) && !deck.main.is_empty() {
+                section = Some(Section::Sideboard);
+            }
+            continue;
+        }
+        if line.starts_with("//") {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("about") {
+            section = None;
+            continue;
+        }
+        if let Some(header) = Section::ALL
+            .into_iter()
+            .find(|header| line.eq_ignore_ascii_case(&header.to_string()))
+        {
+            section = Some(header);
+            continue;
+        }
+
+        let section = match section {
+            Some(section) => section,
+            None => {
+                if let Some(name) = line.strip_prefix("Name ") {
+                    deck.name = Some(name.trim().to_string());
+                }
+                continue;
+            }
+        };
+        match parse_entry(line) {
+            Some(entry) => deck.section_mut(section).push(entry),
+            None => errors.push(DeckError::Syntax {
+                line: index + 1,
+                text: line.to_string(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        deck.take_companion_from_sideboard();
+        Ok(deck)
+    } else {
+        Err(DeckErrors(errors))
+    }
+}
+
+/// Writes the deck in the Arena format, a section per part of the deck the deck has, after the name of the
+/// deck if it has one. Like Arena, the companion is listed in the sideboard too.
+pub fn serialize(deck: &Deck) -> String {
+    let mut sections = Vec::new();
+    if let Some(name) = &deck.name {
+        sections.push(format!("About\nName {}", name));
+    }
+    for section in Section::ALL {
+        let entries = match section {
+            Section::Sideboard => deck.sideboard_with_companion(),
+            _ => deck.section(section).iter().collect(),
+        };
+        if entries.is_empty() {
+            continue;
+        }
+        let mut lines = vec![section.to_string()];
+        lines.extend(entries.iter().map(|entry| entry.to_string()));
+        sections.push(lines.join("\n"));
+    }
+    sections.join("\n\n") + "\n"
+}
+
+/// The Arena code of a Scryfall set code, in uppercase as Arena writes them.
+pub fn set_code(scryfall_set_code: &str) -> String {
+    SET_CODES
+        .iter()
+        .find(|(_, scryfall)| scryfall.eq_ignore_ascii_case(scryfall_set_code))
+        .map_or(scryfall_set_code, |(arena, _)| arena)
+        .to_uppercase()
+}
+
+/// The Scryfall code of an Arena set code, in lowercase as Scryfall writes them.
+fn scryfall_set_code(arena_set_code: &str) -> String {
+    SET_CODES
+        .iter()
+        .find(|(arena, _)| arena.eq_ignore_ascii_case(arena_set_code))
+        .map_or(arena_set_code, |(_, scryfall)| scryfall)
+        .to_lowercase()
+}
+
+/// Parses `4 Name`, `4x Name`, `4 Name (SET)` or `4 Name (SET) 123`.
+fn parse_entry(line: &str) -> Option<DeckEntry> {
+    let (count, rest) = line.split_once(char::is_whitespace)?;
+    let count = count
+        .strip_suffix(['x', 'X'])
+        .unwrap_or(count)
+        .parse()
+        .ok()?;
+    let rest = rest.trim();
+
+    let (name, set, collector_number) = match rest.rsplit_once(" (") {
+        Some((name, printing)) => match printing.split_once(')') {
+            Some((set, collector_number)) if !set.is_empty() => {
+                let collector_number = collector_number.trim();
+                (
+                    name,
+                    Some(scryfall_set_code(set)),
+                    (!collector_number.is_empty()).then(|| collector_number.to_string()),
+                )
+            }
+            _ => (rest, None, None),
+        },
+        None => (rest, None, None),
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(DeckEntry {
+        set,
+        collector_number,
+        ..DeckEntry::new(count, name.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECKLIST: &str = "About
+Name Izzet Tempo
+
+Companion
+1 Lurrus of the Dream-Den (IKO) 226
+
+Deck
+4 Lightning Strike (M19) 152
+4 Opt
+20 Mountain (DAR) 265
+
+Sideboard
+1 Lurrus of the Dream-Den (IKO) 226
+2 Brazen Borrower (ELD) 39
+";
+
+    #[test]
+    fn writes_back_what_it_reads() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(serialize(&deck), DECKLIST);
+        assert_eq!(parse(&serialize(&deck)).unwrap(), deck);
+    }
+
+    #[test]
+    fn reads_the_sections_and_printings() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(deck.name.as_deref(), Some("Izzet Tempo"));
+        assert_eq!(deck.companion.len(), 1);
+        // the copy of the companion in the sideboard is not a sideboard card
+        assert_eq!(deck.sideboard.len(), 1);
+        assert_eq!(deck.sideboard[0].name, "Brazen Borrower");
+
+        let mountain = &deck.main[2];
+        assert_eq!(mountain.count, 20);
+        assert_eq!(mountain.set.as_deref(), Some("dom"));
+        assert_eq!(mountain.collector_number.as_deref(), Some("265"));
+        let opt = &deck.main[1];
+        assert_eq!(
+            (opt.set.as_ref(), opt.collector_number.as_ref()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn reads_the_sideboard_after_an_empty_line_without_headers() {
+        let deck =
+            parse("// exported by an old client\n4x Opt\n2 Shock (M19)\n\n1 Negate\n").unwrap();
+        assert_eq!(deck.main.len(), 2);
+        assert_eq!(deck.main[0].count, 4);
+        assert_eq!(deck.main[1].set.as_deref(), Some("m19"));
+        assert_eq!(deck.main[1].collector_number, None);
+        assert_eq!(deck.sideboard, [DeckEntry::new(1, "Negate")]);
+    }
+
+    #[test]
+    fn fails_with_every_line_that_is_not_a_card() {
+        let errors = parse("Deck\n4 Opt\nOpt\n\nSideboard\nx2 Negate\n").unwrap_err();
+        let lines = errors
+            .0
+            .iter()
+            .map(|error| match error {
+                DeckError::Syntax { line, text } => (*line, text.as_str()),
+                other => panic!("unexpected error {}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(3, "Opt"), (6, "x2 Negate")]);
+    }
+}
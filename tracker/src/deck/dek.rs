@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use super::{Deck, DeckEntry, DeckError, DeckErrors, Section};
+
+/// Parses an MTGO `.dek` file:
+///
+/// ```text
+/// <?xml version="1.0" encoding="utf-8"?>
+/// <Deck xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+///   <NetDeckID>0</NetDeckID>
+///   <PreconstructedDeckID>0</PreconstructedDeckID>
+///   <Cards CatID="68212" Quantity="4" Sideboard="false" Name="Lightning Strike" Annotation="0" />
+///   <Cards CatID="74120" Quantity="2" Sideboard="true" Name="Brazen Borrower" Annotation="0" />
+/// </Deck>
+/// ```
+///
+/// Only the `Cards` elements are read, the `CatID` being the MTGO id of the printing. MTGO has no commander
+/// or companion section, so they end up in the sideboard. Fails with every `Cards` element without a
+/// quantity or a name.
+pub fn parse(decklist: &str) -> Result<Deck, DeckErrors> {
+    let mut deck = Deck::default();
+    let mut errors = Vec::new();
+    for (start, _) in decklist.match_indices("<Cards") {
+        let element = &decklist[start..];
+        let element = match element.find('>') {
+            Some(end) => &element[..=end],
+            None => element,
+        };
+        match parse_entry(element) {
+            Some((section, entry)) => deck.section_mut(section).push(entry),
+            None => errors.push(DeckError::Syntax {
+                line: decklist[..start].matches('\n').count() + 1,
+                text: element.to_string(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(deck)
+    } else {
+        Err(DeckErrors(errors))
+    }
+}
+
+/// Writes the deck as an MTGO `.dek` file, the commander and the companion in the sideboard, the companion
+/// only if it is not there already. The `CatID` is left out of the cards with no MTGO id.
+pub fn serialize(deck: &Deck) -> String {
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="utf-8"?>"#.to_string(),
+        r#"<Deck xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">"#.to_string(),
+        "  <NetDeckID>0</NetDeckID>".to_string(),
+        "  <PreconstructedDeckID>0</PreconstructedDeckID>".to_string(),
+    ];
+    let entries = deck
+        .commander
+        .iter()
+        .map(|entry| (entry, true))
+        .chain(deck.main.iter().map(|entry| (entry, false)))
+        .chain(
+            deck.sideboard_with_companion()
+                .into_iter()
+                .map(|entry| (entry, true)),
+        );
+    for (entry, sideboard) in entries {
+        let cat_id = entry
+            .mtgo_id
+            .map_or(String::new(), |mtgo_id| format!(r#"CatID="{}" "#, mtgo_id));
+        lines.push(format!(
+            r#"  <Cards {}Quantity="{}" Sideboard="{}" Name="{}" Annotation="0" />"#,
+            cat_id,
+            entry.count,
+            sideboard,
+            escape(&entry.name.replace(" // ", "/"))
+        ));
+    }
+    lines.push("</Deck>".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Parses a `Cards` element. MTGO names split cards like `Fire/Ice`, where Scryfall and Arena write
+/// `Fire // Ice`.
+fn parse_entry(element: &str) -> Option<(Section, DeckEntry)> {
+    let attributes = parse_attributes(element.strip_prefix("<Cards")?)?;
+    let count = attributes.get("Quantity")?.parse().ok()?;
+    let name = attributes.get("Name")?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let name = if name.contains(" // ") {
+        name.to_string()
+    } else {
+        name.replace('/', " // ")
+    };
+    let section = match attributes.get("Sideboard").map(String::as_str) {
+        Some("true") => Section::Sideboard,
+        _ => Section::Main,
+    };
+
+    Some((
+        section,
+        DeckEntry {
+            mtgo_id: attributes
+                .get("CatID")
+                .and_then(|cat_id| cat_id.parse().ok()),
+            ..DeckEntry::new(count, &name)
+        },
+    ))
+}
+
+/// Parses the `name="value"` attributes of an element, up to its end.
+fn parse_attributes(mut element: &str) -> Option<HashMap<&str, String>> {
+    let mut attributes = HashMap::new();
+    loop {
+        element = element.trim_start();
+        if element.is_empty() || element.starts_with("/>") || element.starts_with('>') {
+            return Some(attributes);
+        }
+        let (name, rest) = element.split_once('=')?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, rest) = rest[1..].split_once(quote)?;
+        attributes.insert(name.trim(), unescape(value));
+        element = rest;
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECKLIST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Deck xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <NetDeckID>0</NetDeckID>
+  <PreconstructedDeckID>0</PreconstructedDeckID>
+  <Cards CatID="68212" Quantity="4" Sideboard="false" Name="Lightning Strike" Annotation="0" />
+  <Cards CatID="20342" Quantity="2" Sideboard="false" Name="Fire/Ice" Annotation="0" />
+  <Cards Quantity="20" Sideboard="false" Name="Mountain" Annotation="0" />
+  <Cards CatID="74120" Quantity="2" Sideboard="true" Name="Brazen Borrower" Annotation="0" />
+</Deck>
+"#;
+
+    #[test]
+    fn writes_back_what_it_reads() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(serialize(&deck), DECKLIST);
+        assert_eq!(parse(&serialize(&deck)).unwrap(), deck);
+    }
+
+    #[test]
+    fn reads_the_mtgo_ids_and_split_card_names() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(deck.main.len(), 3);
+        assert_eq!(deck.main[0].mtgo_id, Some(68212));
+        assert_eq!(deck.main[1].name, "Fire // Ice");
+        assert_eq!(deck.main[2].mtgo_id, None);
+        assert_eq!(deck.sideboard[0].count, 2);
+    }
+
+    #[test]
+    fn escapes_the_names() {
+        let deck = Deck {
+            main: vec![DeckEntry::new(1, "\"Ach! Hans, Run!\"")],
+            ..Deck::default()
+        };
+        let decklist = serialize(&deck);
+        assert!(decklist.contains(r#"Name="&quot;Ach! Hans, Run!&quot;""#));
+        assert_eq!(parse(&decklist).unwrap(), deck);
+    }
+
+    #[test]
+    fn fails_with_every_card_without_a_quantity_or_a_name() {
+        let decklist = r#"<Deck>
+  <Cards Quantity="four" Sideboard="false" Name="Opt" />
+  <Cards Quantity="1" Sideboard="false" Name="Negate" />
+  <Cards Quantity="1" Sideboard="false" Name="" />
+</Deck>"#;
+        let lines = parse(decklist)
+            .unwrap_err()
+            .0
+            .iter()
+            .map(|error| match error {
+                DeckError::Syntax { line, .. } => *line,
+                other => panic!("unexpected error {}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [2, 4]);
+    }
+}
@@ -0,0 +1,171 @@
+use super::{Deck, DeckEntry, DeckError, DeckErrors, Section};
+
+/// Parses a Forge `.dck` file:
+///
+/// ```text
+/// [metadata]
+/// Name=Izzet Tempo
+/// [Main]
+/// 4 Lightning Strike|M19
+/// 20 Mountain|DMU|2
+/// [Sideboard]
+/// 2 Brazen Borrower|ELD
+/// ```
+///
+/// The set is optional, and the art index after it is ignored. Forge writes the Scryfall set codes in
+/// uppercase. The commander comes from the `[Commander]` section, the other sections, like `[Planes]`, are
+/// skipped. Fails with every card line that cannot be parsed.
+pub fn parse(decklist: &str) -> Result<Deck, DeckErrors> {
+    let mut deck = Deck::default();
+    let mut errors = Vec::new();
+    // None in the metadata and the skipped sections
+    let mut section = None;
+    let mut metadata = false;
+    for (index, line) in decklist.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            metadata = header.eq_ignore_ascii_case("metadata");
+            section = match header.to_lowercase().as_str() {
+                "commander" => Some(Section::Commander),
+                "main" => Some(Section::Main),
+                "sideboard" => Some(Section::Sideboard),
+                _ => None,
+            };
+            continue;
+        }
+
+        let section = match section {
+            Some(section) => section,
+            None => {
+                if let Some(name) = line.strip_prefix("Name=").filter(|_| metadata) {
+                    deck.name = Some(name.trim().to_string());
+                }
+                continue;
+            }
+        };
+        match parse_entry(line) {
+            Some(entry) => deck.section_mut(section).push(entry),
+            None => errors.push(DeckError::Syntax {
+                line: index + 1,
+                text: line.to_string(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(deck)
+    } else {
+        Err(DeckErrors(errors))
+    }
+}
+
+/// Writes the deck as a Forge `.dck` file. Forge has no companion section, so the companion goes in the
+/// sideboard, unless it is there already.
+pub fn serialize(deck: &Deck) -> String {
+    let mut lines = vec!["[metadata]".to_string()];
+    if let Some(name) = &deck.name {
+        lines.push(format!("Name={}", name));
+    }
+    let sections = [
+        ("Commander", deck.commander.iter().collect::<Vec<_>>()),
+        ("Main", deck.main.iter().collect()),
+        ("Sideboard", deck.sideboard_with_companion()),
+    ];
+    for (header, entries) in sections {
+        if entries.is_empty() && header != "Main" {
+            continue;
+        }
+        lines.push(format!("[{}]", header));
+        for entry in entries {
+            match &entry.set {
+                Some(set) => lines.push(format!(
+                    "{} {}|{}",
+                    entry.count,
+                    entry.name,
+                    set.to_uppercase()
+                )),
+                None => lines.push(format!("{} {}", entry.count, entry.name)),
+            }
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Parses `4 Name`, `4 Name|SET` or `4 Name|SET|1`.
+fn parse_entry(line: &str) -> Option<DeckEntry> {
+    let (count, rest) = line.split_once(char::is_whitespace)?;
+    let count = count.parse().ok()?;
+    let mut fields = rest.split('|');
+    let name = fields.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let set = fields
+        .next()
+        .map(str::trim)
+        .filter(|set| !set.is_empty())
+        .map(str::to_lowercase);
+
+    Some(DeckEntry {
+        set,
+        ..DeckEntry::new(count, name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECKLIST: &str = "[metadata]
+Name=Izzet Tempo
+[Commander]
+1 Niv-Mizzet, Parun|GRN
+[Main]
+4 Lightning Strike|M19
+20 Mountain|DMU
+4 Opt
+[Sideboard]
+2 Brazen Borrower|ELD
+";
+
+    #[test]
+    fn writes_back_what_it_reads() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(serialize(&deck), DECKLIST);
+        assert_eq!(parse(&serialize(&deck)).unwrap(), deck);
+    }
+
+    #[test]
+    fn reads_the_sections_and_skips_the_others() {
+        let deck = parse(
+            "[metadata]\nName=Test\n[Main]\n4 Opt|xln|1\n[Planes]\n1 Naya|HOP\n[sideboard]\n# a comment\n1 Negate\n",
+        )
+        .unwrap();
+        assert_eq!(deck.name.as_deref(), Some("Test"));
+        assert_eq!(deck.main.len(), 1);
+        assert_eq!(deck.main[0].set.as_deref(), Some("xln"));
+        // the art index is not a collector number
+        assert_eq!(deck.main[0].collector_number, None);
+        assert_eq!(deck.sideboard, [DeckEntry::new(1, "Negate")]);
+    }
+
+    #[test]
+    fn fails_with_every_line_that_is_not_a_card() {
+        let lines = parse("[Main]\n4 Opt\nOpt\n4 |M19\n")
+            .unwrap_err()
+            .0
+            .iter()
+            .map(|error| match error {
+                DeckError::Syntax { line, text } => (*line, text.clone()),
+                other => panic!("unexpected error {}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(3, "Opt".to_string()), (4, "4 |M19".to_string())]);
+    }
+}
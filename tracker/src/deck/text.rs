@@ -0,0 +1,68 @@
+use super::{arena, Deck, DeckEntry, DeckErrors};
+
+/// Parses a plain decklist, a `4 Lightning Strike` line per card and the sideboard after an empty line. It is
+/// the Arena format without printings, so the Arena parser reads it, and any printing given is kept.
+pub fn parse(decklist: &str) -> Result<Deck, DeckErrors> {
+    arena::parse(decklist)
+}
+
+/// Writes the deck as a plain decklist, names only: the commander at the top of the main deck, the companion
+/// in the sideboard unless it is there already.
+pub fn serialize(deck: &Deck) -> String {
+    let line = |entry: &DeckEntry| format!("{} {}", entry.count, entry.name);
+    let main = deck.commander.iter().chain(&deck.main).map(line);
+    let sideboard = deck
+        .sideboard_with_companion()
+        .into_iter()
+        .map(line)
+        .collect::<Vec<_>>();
+
+    let mut lines = main.collect::<Vec<_>>();
+    if !sideboard.is_empty() {
+        lines.push(String::new());
+        lines.extend(sideboard);
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::DeckError;
+
+    const DECKLIST: &str = "4 Lightning Strike
+4 Opt
+
+2 Brazen Borrower
+";
+
+    #[test]
+    fn writes_back_what_it_reads() {
+        let deck = parse(DECKLIST).unwrap();
+        assert_eq!(serialize(&deck), DECKLIST);
+        assert_eq!(parse(&serialize(&deck)).unwrap(), deck);
+    }
+
+    #[test]
+    fn writes_the_commander_and_the_companion_with_the_other_cards() {
+        let deck = Deck {
+            commander: vec![DeckEntry::new(1, "Niv-Mizzet, Parun")],
+            companion: vec![DeckEntry::new(1, "Lurrus of the Dream-Den")],
+            main: vec![DeckEntry::new(4, "Opt")],
+            ..Deck::default()
+        };
+        assert_eq!(
+            serialize(&deck),
+            "1 Niv-Mizzet, Parun\n4 Opt\n\n1 Lurrus of the Dream-Den\n"
+        );
+    }
+
+    #[test]
+    fn fails_with_every_line_that_is_not_a_card() {
+        let errors = parse("4 Opt\nfour Negate\n").unwrap_err();
+        assert!(matches!(
+            errors.0.as_slice(),
+            [DeckError::Syntax { line: 2, .. }]
+        ));
+    }
+}
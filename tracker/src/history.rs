@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::collector::model::{Collection, Inventory, InventoryUpdate};
+use crate::collector::{CollectorEvent, LogEntry};
+use crate::database;
+
+/// The collection and inventory history of the players, stored next to the card database in the tracker
+/// database.
+///
+/// Collection and inventory snapshots are deduplicated: as long as they do not change, the periodic snapshots
+/// only extend the time range of the last stored snapshot. Events older than the newest stored one are
+/// skipped, so ingesting the same Player.log again does not duplicate anything.
+pub struct History {
+    db: Connection,
+    account_id: String,
+}
+
+/// The time range a stored collection snapshot was seen in.
+pub struct CollectionSnapshot {
+    pub account_id: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub collection: Collection,
+}
+
+/// The time range a stored inventory snapshot was seen in.
+pub struct InventorySnapshot {
+    pub account_id: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub inventory: Inventory,
+}
+
+/// A point in the history where a card showed up.
+pub struct Acquisition {
+    pub account_id: String,
+    pub timestamp: String,
+    pub source: String,
+}
+
+impl History {
+    pub fn open<P>(path: P) -> Result<History, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let db = database::open(path)?;
+
+        Ok(History {
+            db,
+            account_id: String::new(),
+        })
+    }
+
+    /// Stores the event if it carries history. Returns true if something new was written. The snapshots and
+    /// inventory updates logged before the account info are skipped, as they belong to no known account.
+    pub fn record(&mut self, event: &CollectorEvent) -> Result<bool, Box<dyn Error>> {
+        match event {
+            CollectorEvent::AccountInfo(info) => {
+                self.account_id = info.user_id.clone();
+                Ok(false)
+            }
+            CollectorEvent::Collection(_)
+            | CollectorEvent::Inventory(_)
+            | CollectorEvent::InventoryUpdate(_)
+                if self.account_id.is_empty() =>
+            {
+                Ok(false)
+            }
+            CollectorEvent::Collection(entry) => self.record_collection(entry),
+            CollectorEvent::Inventory(entry) => self.record_inventory(entry),
+            CollectorEvent::InventoryUpdate(entry) => self.record_inventory_update(entry),
+            _ => Ok(false),
+        }
+    }
+
+    fn record_collection(&mut self, entry: &LogEntry<Collection>) -> Result<bool, Box<dyn Error>> {
+        let timestamp = database::timestamp(&entry.timestamp);
+        let tx = self.db.transaction()?;
+
+        let latest: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT id, last_seen FROM collection_snapshots
+                WHERE account_id = ?1 ORDER BY last_seen DESC LIMIT 1",
+                params![self.account_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        if let Some((snapshot_id, last_seen)) = latest {
+            if timestamp <= last_seen {
+                return Ok(false);
+            }
+
+            if History::load_collection(&tx, snapshot_id)? == entry.attachment {
+                tx.execute(
+                    "UPDATE collection_snapshots SET last_seen = ?1 WHERE id = ?2",
+                    params![timestamp, snapshot_id],
+                )?;
+                tx.commit()?;
+                return Ok(false);
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO collection_snapshots ('account_id', 'first_seen', 'last_seen') VALUES (?1, ?2, ?2)",
+            params![self.account_id, timestamp],
+        )?;
+        let snapshot_id = tx.last_insert_rowid();
+
+        {
+            let mut insert_card = tx.prepare(
+                "INSERT INTO collection_cards ('snapshot_id', 'arena_id', 'count') VALUES (?1, ?2, ?3)",
+            )?;
+            for (arena_id, count) in entry.attachment.iter() {
+                insert_card.execute(params![snapshot_id, arena_id, count])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn record_inventory(&mut self, entry: &LogEntry<Inventory>) -> Result<bool, Box<dyn Error>> {
+        let timestamp = database::timestamp(&entry.timestamp);
+        let inventory = &entry.attachment;
+        let data = serde_json::to_value(inventory)?;
+        let tx = self.db.transaction()?;
+
+        let latest: Option<(i64, String, serde_json::Value)> = tx
+            .query_row(
+                "SELECT id, last_seen, data FROM inventory_snapshots
+                WHERE account_id = ?1 ORDER BY last_seen DESC LIMIT 1",
+                params![self.account_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        if let Some((snapshot_id, last_seen, last_data)) = latest {
+            if timestamp <= last_seen {
+                return Ok(false);
+            }
+
+            if last_data == data {
+                tx.execute(
+                    "UPDATE inventory_snapshots SET last_seen = ?1 WHERE id = ?2",
+                    params![timestamp, snapshot_id],
+                )?;
+                tx.commit()?;
+                return Ok(false);
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO inventory_snapshots ('account_id', 'first_seen', 'last_seen', 'gold', 'gems', 'wc_common',
+            'wc_uncommon', 'wc_rare', 'wc_mythic', 'vault_progress', 'data')
+            VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                self.account_id,
+                timestamp,
+                inventory.gold,
+                inventory.gems,
+                inventory.wc_common,
+                inventory.wc_uncommon,
+                inventory.wc_rare,
+                inventory.wc_mythic,
+                inventory.vault_progress,
+                data
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn record_inventory_update(
+        &mut self,
+        entry: &LogEntry<InventoryUpdate>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let update = &entry.attachment;
+        let delta = &update.delta;
+        let tx = self.db.transaction()?;
+
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO inventory_updates ('account_id', 'timestamp', 'source', 'gold_delta', 'gems_delta',
+            'wc_common_delta', 'wc_uncommon_delta', 'wc_rare_delta', 'wc_mythic_delta', 'vault_progress_delta', 'data')
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                self.account_id,
+                database::timestamp(&entry.timestamp),
+                update.context.as_ref().and_then(|context| context.source()),
+                delta.gold_delta,
+                delta.gems_delta,
+                delta.wc_common_delta,
+                delta.wc_uncommon_delta,
+                delta.wc_rare_delta,
+                delta.wc_mythic_delta,
+                delta.vault_progress_delta,
+                serde_json::to_value(update)?
+            ],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let update_id = tx.last_insert_rowid();
+        {
+            let mut insert_card = tx.prepare(
+                "INSERT INTO inventory_update_cards ('update_id', 'arena_id') VALUES (?1, ?2)",
+            )?;
+            for arena_id in &delta.cards_added {
+                insert_card.execute(params![update_id, arena_id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Returns the account whose collection or inventory was seen last.
+    pub fn latest_account(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let account_id = self
+            .db
+            .query_row(
+                "SELECT account_id FROM (
+                    SELECT account_id, last_seen FROM collection_snapshots
+                    UNION ALL
+                    SELECT account_id, last_seen FROM inventory_snapshots
+                ) ORDER BY last_seen DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(account_id)
+    }
+
+    /// Returns the collection of the account as it was at the given point in time.
+    pub fn collection_at<Tz>(
+        &self,
+        account_id: &str,
+        at: &DateTime<Tz>,
+    ) -> Result<Option<CollectionSnapshot>, Box<dyn Error>>
+    where
+        Tz: TimeZone,
+    {
+        let snapshot: Option<(i64, String, String, String)> = self
+            .db
+            .query_row(
+                "SELECT id, account_id, first_seen, last_seen FROM collection_snapshots
+                WHERE account_id = ?1 AND first_seen <= ?2 ORDER BY first_seen DESC LIMIT 1",
+                params![account_id, database::timestamp(at)],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+
+        match snapshot {
+            Some((snapshot_id, account_id, first_seen, last_seen)) => {
+                Ok(Some(CollectionSnapshot {
+                    account_id,
+                    first_seen,
+                    last_seen,
+                    collection: History::load_collection(&self.db, snapshot_id)?,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the inventory snapshots of the account in chronological order, the last one is the current
+    /// inventory.
+    pub fn inventory_snapshots(
+        &self,
+        account_id: &str,
+    ) -> Result<Vec<InventorySnapshot>, Box<dyn Error>> {
+        let mut statement = self.db.prepare(
+            "SELECT account_id, first_seen, last_seen, data FROM inventory_snapshots
+            WHERE account_id = ?1 ORDER BY first_seen",
+        )?;
+        let snapshots = statement
+            .query_map(params![account_id], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get::<_, serde_json::Value>(3)?,
+                ))
+            })?
+            .map(|row| {
+                let (account_id, first_seen, last_seen, data) = row?;
+                Ok(InventorySnapshot {
+                    account_id,
+                    first_seen,
+                    last_seen,
+                    inventory: serde_json::from_value(data)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(snapshots)
+    }
+
+    /// Returns the current inventory of the account, its last inventory snapshot.
+    pub fn latest_inventory(
+        &self,
+        account_id: &str,
+    ) -> Result<Option<InventorySnapshot>, Box<dyn Error>> {
+        let snapshot: Option<(String, String, serde_json::Value)> = self
+            .db
+            .query_row(
+                "SELECT first_seen, last_seen, data FROM inventory_snapshots
+                WHERE account_id = ?1 ORDER BY last_seen DESC LIMIT 1",
+                params![account_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        match snapshot {
+            Some((first_seen, last_seen, data)) => Ok(Some(InventorySnapshot {
+                account_id: account_id.to_string(),
+                first_seen,
+                last_seen,
+                inventory: serde_json::from_value(data)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns when the card first showed up in the collection snapshots, and every inventory update that added
+    /// it, in chronological order.
+    pub fn acquisitions(&self, arena_id: u32) -> Result<Vec<Acquisition>, Box<dyn Error>> {
+        let mut statement = self.db.prepare(
+            "SELECT account_id, MIN(first_seen), 'first collection snapshot' FROM collection_snapshots
+            JOIN collection_cards ON collection_cards.snapshot_id = collection_snapshots.id
+            WHERE collection_cards.arena_id = ?1
+            GROUP BY account_id
+            UNION ALL
+            SELECT account_id, timestamp, IFNULL(source, 'unknown source') FROM inventory_updates
+            JOIN inventory_update_cards ON inventory_update_cards.update_id = inventory_updates.id
+            WHERE inventory_update_cards.arena_id = ?1
+            ORDER BY 2",
+        )?;
+
+        let acquisitions = statement
+            .query_map(params![arena_id], |row| {
+                Ok(Acquisition {
+                    account_id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    source: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(acquisitions)
+    }
+
+    /// The cards added by opening boosters, by the collation id of the boosters.
+    pub fn opened_boosters(&self) -> Result<HashMap<u32, Vec<u32>>, Box<dyn Error>> {
+        let mut statement = self.db.prepare(
+            "SELECT CAST(json_extract(data, '$.context.sourceId') AS INTEGER), arena_id FROM inventory_updates
+            JOIN inventory_update_cards ON inventory_update_cards.update_id = inventory_updates.id
+            WHERE source = 'BoosterOpen' AND json_extract(data, '$.context.sourceId') IS NOT NULL",
+        )?;
+
+        let mut opened = HashMap::new();
+        for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (collation_id, arena_id): (u32, u32) = row?;
+            opened
+                .entry(collation_id)
+                .or_insert_with(Vec::new)
+                .push(arena_id);
+        }
+        Ok(opened)
+    }
+
+    /// Every card that was in a collection snapshot, with the most copies ever owned of it.
+    pub fn seen_arena_ids(&self) -> Result<Vec<(u32, u32)>, Box<dyn Error>> {
+        let mut statement = self.db.prepare(
+            "SELECT arena_id, MAX(count) FROM collection_cards GROUP BY arena_id ORDER BY arena_id",
+        )?;
+        let seen = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(seen)
+    }
+
+    fn load_collection(db: &Connection, snapshot_id: i64) -> Result<Collection, Box<dyn Error>> {
+        let mut statement =
+            db.prepare("SELECT arena_id, count FROM collection_cards WHERE snapshot_id = ?1")?;
+        let collection = statement
+            .query_map(params![snapshot_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Collection, _>>()?;
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempFile;
+
+    fn event(line: &str) -> CollectorEvent {
+        CollectorEvent::from_line(line).unwrap().unwrap()
+    }
+
+    fn account_info() -> CollectorEvent {
+        event("[MTGADataCollector][account-info]{ UserId = ABCDEF123, ScreenName = Ojji#12345 }")
+    }
+
+    fn collection(timestamp: &str, cards: &str) -> CollectorEvent {
+        event(&format!(
+            "[MTGADataCollector][collection]{{\"Timestamp\":\"{}\",\"Attachment\":{}}}",
+            timestamp, cards
+        ))
+    }
+
+    /// The account, first and last time seen of the stored collection snapshots, oldest first.
+    fn snapshots(history: &History) -> Vec<(String, String, String)> {
+        let mut statement = history
+            .db
+            .prepare(
+                "SELECT account_id, first_seen, last_seen FROM collection_snapshots ORDER BY id",
+            )
+            .unwrap();
+        let snapshots = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        snapshots
+    }
+
+    fn snapshot(first_seen: &str, last_seen: &str) -> (String, String, String) {
+        (
+            "ABCDEF123".to_string(),
+            first_seen.to_string(),
+            last_seen.to_string(),
+        )
+    }
+
+    #[test]
+    fn collapses_identical_consecutive_collections() {
+        let file = TempFile::new("history-collapse.db");
+        let mut history = History::open(&file.0).unwrap();
+        history.record(&account_info()).unwrap();
+
+        let cards = r#"{"75000":4,"75001":2}"#;
+        assert!(history
+            .record(&collection("2022-10-10T21:36:12+02:00", cards))
+            .unwrap());
+        assert!(!history
+            .record(&collection("2022-10-10T21:46:12+02:00", cards))
+            .unwrap());
+        assert!(!history
+            .record(&collection(
+                "2022-10-10T21:56:12+02:00",
+                r#"{"75001":2,"75000":4}"#
+            ))
+            .unwrap());
+        assert_eq!(
+            snapshots(&history),
+            [snapshot(
+                "2022-10-10T19:36:12.000000Z",
+                "2022-10-10T19:56:12.000000Z"
+            )]
+        );
+    }
+
+    #[test]
+    fn ignores_replayed_and_older_collections() {
+        let file = TempFile::new("history-replay.db");
+        let mut history = History::open(&file.0).unwrap();
+        history.record(&account_info()).unwrap();
+        history
+            .record(&collection("2022-10-10T21:36:12+02:00", r#"{"75000":4}"#))
+            .unwrap();
+        history
+            .record(&collection("2022-10-10T21:46:12+02:00", r#"{"75000":4}"#))
+            .unwrap();
+
+        assert!(!history
+            .record(&collection("2022-10-10T21:46:12+02:00", r#"{"75000":4}"#))
+            .unwrap());
+        assert!(!history
+            .record(&collection("2022-10-10T21:40:12+02:00", r#"{"75000":1}"#))
+            .unwrap());
+        assert_eq!(
+            snapshots(&history),
+            [snapshot(
+                "2022-10-10T19:36:12.000000Z",
+                "2022-10-10T19:46:12.000000Z"
+            )]
+        );
+    }
+
+    #[test]
+    fn starts_a_new_snapshot_when_the_collection_changes() {
+        let file = TempFile::new("history-change.db");
+        let mut history = History::open(&file.0).unwrap();
+        history.record(&account_info()).unwrap();
+        history
+            .record(&collection("2022-10-10T21:36:12+02:00", r#"{"75000":4}"#))
+            .unwrap();
+
+        assert!(history
+            .record(&collection(
+                "2022-10-11T21:36:12+02:00",
+                r#"{"75000":4,"75001":1}"#
+            ))
+            .unwrap());
+        assert_eq!(
+            snapshots(&history),
+            [
+                snapshot("2022-10-10T19:36:12.000000Z", "2022-10-10T19:36:12.000000Z"),
+                snapshot("2022-10-11T19:36:12.000000Z", "2022-10-11T19:36:12.000000Z")
+            ]
+        );
+
+        let at = DateTime::parse_from_rfc3339("2022-10-11T12:00:00Z").unwrap();
+        let before = history.collection_at("ABCDEF123", &at).unwrap().unwrap();
+        assert_eq!(before.collection, [(75000, 4)].into_iter().collect());
+        let at = DateTime::parse_from_rfc3339("2022-10-12T12:00:00Z").unwrap();
+        let after = history.collection_at("ABCDEF123", &at).unwrap().unwrap();
+        assert_eq!(
+            after.collection,
+            [(75000, 4), (75001, 1)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn skips_the_collections_before_the_account_is_known() {
+        let file = TempFile::new("history-no-account.db");
+        let mut history = History::open(&file.0).unwrap();
+
+        assert!(!history
+            .record(&collection("2022-10-10T21:36:12+02:00", r#"{"75000":4}"#))
+            .unwrap());
+        assert!(snapshots(&history).is_empty());
+        assert_eq!(history.latest_account().unwrap(), None);
+    }
+}
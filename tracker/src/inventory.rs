@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::collector::model::Inventory;
+use crate::history::InventorySnapshot;
+use crate::mtgadb::MtgaDb;
+
+/// The widest bar of the history plot.
+const PLOT_WIDTH: usize = 50;
+
+/// The values of the inventory the history can be plotted for.
+pub const SERIES: [&str; 7] = [
+    "common", "uncommon", "rare", "mythic", "gold", "gems", "vault",
+];
+
+/// Prints the inventory. The boosters the client sends no set code for get the set from `booster_sets`, by
+/// collation id.
+pub fn print_inventory(snapshot: &InventorySnapshot, booster_sets: &HashMap<u32, String>) {
+    let inventory = &snapshot.inventory;
+    println!(
+        "Inventory of {} seen from {} to {}:",
+        snapshot.account_id, snapshot.first_seen, snapshot.last_seen
+    );
+    println!("Gold: {}, gems: {}", inventory.gold, inventory.gems);
+    println!(
+        "Wildcards: {} common, {} uncommon, {} rare, {} mythic",
+        inventory.wc_common, inventory.wc_uncommon, inventory.wc_rare, inventory.wc_mythic
+    );
+    println!("Vault: {:.1}%", inventory.vault_progress);
+    println!(
+        "Draft tokens: {}, sealed tokens: {}",
+        inventory.draft_tokens, inventory.sealed_tokens
+    );
+
+    // the client can list a kind of booster more than once
+    let mut boosters = BTreeMap::new();
+    for booster in inventory
+        .boosters
+        .iter()
+        .filter(|booster| booster.count > 0)
+    {
+        let set = match &booster.set_code {
+            Some(set_code) => set_code.to_uppercase(),
+            None => match booster_sets.get(&booster.collation_id) {
+                Some(set) => set.clone(),
+                None => format!("collation {}", booster.collation_id),
+            },
+        };
+        *boosters.entry(set).or_insert(0) += booster.count;
+    }
+    if boosters.is_empty() {
+        println!("No unopened boosters.");
+    } else {
+        println!("Unopened boosters:");
+        for (set, count) in boosters {
+            println!("{:>16} {:>4}", set, count);
+        }
+    }
+}
+
+/// Finds the set of the boosters of each collation from the cards opened from them: the set most of the
+/// cards belong to, in the card database or else in the client's card database.
+pub fn booster_sets(opened: &HashMap<u32, Vec<u32>>, database: &MtgaDb) -> HashMap<u32, String> {
+    opened
+        .iter()
+        .filter_map(|(collation_id, arena_ids)| {
+            let mut sets = BTreeMap::new();
+            for arena_id in arena_ids {
+                let set = match database.card(*arena_id) {
+                    Some(card) => Some(&card.set),
+                    None => database.client_cards().get(arena_id).map(|card| &card.set),
+                };
+                if let Some(set) = set {
+                    *sets.entry(set.to_uppercase()).or_insert(0) += 1;
+                }
+            }
+            let (set, _) = sets.into_iter().max_by_key(|(_, count)| *count)?;
+            Some((*collation_id, set))
+        })
+        .collect()
+}
+
+/// Plots one value of the inventory per day, from the last snapshot of each day, with the change from the
+/// previous day.
+pub fn plot_history(snapshots: &[InventorySnapshot], series: &str) {
+    let days = daily_values(snapshots, series);
+
+    // only the vault progress has decimals
+    let precision = if series == "vault" { 1 } else { 0 };
+    let max = days.values().copied().fold(0.0, f64::max);
+    println!("{} per day:", series);
+    let mut previous = None;
+    for (day, value) in days {
+        let bar = if max > 0.0 {
+            (value / max * PLOT_WIDTH as f64).round() as usize
+        } else {
+            0
+        };
+        let change = match previous {
+            Some(previous) if value != previous => {
+                format!("{:+.*}", precision, value - previous)
+            }
+            _ => String::new(),
+        };
+        println!(
+            "{} {:>8.*} {:<width$} {}",
+            day,
+            precision,
+            value,
+            "#".repeat(bar),
+            change,
+            width = PLOT_WIDTH
+        );
+        previous = Some(value);
+    }
+}
+
+/// The value of the last snapshot of each day, by the UTC date the snapshot was last seen on.
+fn daily_values<'a>(snapshots: &'a [InventorySnapshot], series: &str) -> BTreeMap<&'a str, f64> {
+    let mut days = BTreeMap::new();
+    for snapshot in snapshots {
+        let day = snapshot.last_seen.get(..10).unwrap_or(&snapshot.last_seen);
+        days.insert(day, series_value(&snapshot.inventory, series));
+    }
+    days
+}
+
+fn series_value(inventory: &Inventory, series: &str) -> f64 {
+    match series {
+        "common" => inventory.wc_common as f64,
+        "uncommon" => inventory.wc_uncommon as f64,
+        "rare" => inventory.wc_rare as f64,
+        "mythic" => inventory.wc_mythic as f64,
+        "gold" => inventory.gold as f64,
+        "gems" => inventory.gems as f64,
+        _ => inventory.vault_progress,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::collector::model::ArenaCard;
+    use crate::testing::card;
+
+    #[test]
+    fn finds_the_set_most_opened_cards_belong_to() {
+        let database = MtgaDb::from_cards(
+            vec![
+                card(json!({ "arena_id": 1 })),
+                card(json!({ "arena_id": 2 })),
+                card(json!({ "arena_id": 3, "set": "eld" })),
+            ],
+            vec![ArenaCard {
+                grp_id: 50,
+                set: "SNC".to_string(),
+                ..ArenaCard::default()
+            }],
+        );
+        let opened = HashMap::from([(100, vec![1, 3, 2]), (200, vec![50, 999]), (300, vec![999])]);
+        assert_eq!(
+            booster_sets(&opened, &database),
+            HashMap::from([(100, "M19".to_string()), (200, "SNC".to_string())])
+        );
+    }
+
+    fn snapshot(last_seen: &str, wc_rare: i32, vault_progress: f64) -> InventorySnapshot {
+        InventorySnapshot {
+            account_id: "ABCDEF123".to_string(),
+            first_seen: last_seen.to_string(),
+            last_seen: last_seen.to_string(),
+            inventory: Inventory {
+                wc_rare,
+                vault_progress,
+                ..Inventory::default()
+            },
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_value_of_each_day() {
+        let snapshots = [
+            snapshot("2022-10-10T08:00:00.000000Z", 1, 10.0),
+            snapshot("2022-10-10T23:59:59.000000Z", 2, 12.5),
+            snapshot("2022-10-12T00:00:00.000000Z", 5, 20.0),
+        ];
+        assert_eq!(
+            daily_values(&snapshots, "rare"),
+            BTreeMap::from([("2022-10-10", 2.0), ("2022-10-12", 5.0)])
+        );
+        assert_eq!(
+            daily_values(&snapshots, "vault"),
+            BTreeMap::from([("2022-10-10", 12.5), ("2022-10-12", 20.0)])
+        );
+        assert!(daily_values(&[], "gold").is_empty());
+    }
+}
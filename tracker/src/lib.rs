@@ -1,9 +1,29 @@
+mod collector;
+mod completion;
 mod configuration;
+mod database;
+mod deck;
+mod history;
+mod inventory;
 mod mtgadb;
+mod prices;
+#[cfg(test)]
+mod testing;
 
+use chrono::{DateTime, Utc};
+use collector::model::ArenaCard;
+use collector::{CollectorEvent, CollectorLog, EventResult};
+use configuration::{CardQuery, ReportFormat};
+use deck::{Deck, DeckErrors, DeckFormat, Wildcards};
+use history::{CollectionSnapshot, History};
 use injector::Mtga;
-use mtgadb::MtgaDb;
+use mtgadb::model::{Legality, ScryCard};
+use mtgadb::{Changelog, Localization, MtgaDb, SetFilter};
+use std::collections::{HashMap, HashSet};
 use std::env::Args;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
 
 pub struct Tracker {
     config: configuration::Config,
@@ -23,14 +43,71 @@ impl Tracker {
                 println!("Data collector injected successfully.");
                 Ok(())
             }
-            configuration::TrackerCommand::CreateDatabase(database_path, required_sets) => {
+            configuration::TrackerCommand::CreateDatabase {
+                scryfall_db_path,
+                set_filter,
+                arena_log_path,
+                update,
+                dry_run,
+            } => {
+                let client_cards = match arena_log_path {
+                    Some(path) => Tracker::read_client_cards(path)?,
+                    None => Vec::new(),
+                };
+
                 let start = std::time::Instant::now();
-                let database = MtgaDb::create_from_scryfall_db(database_path, &required_sets)?;
+                let database = MtgaDb::create_from_scryfall_db(
+                    scryfall_db_path,
+                    set_filter,
+                    client_cards,
+                    |progress| {
+                        print!(
+                            "\r[{:.2?}] Reading Scryfall database: {:>5.1}%, {} cards read, {} arena cards kept",
+                            start.elapsed(),
+                            progress.bytes_read as f64 * 100.0 / progress.total_bytes.max(1) as f64,
+                            progress.cards_read,
+                            progress.cards_kept
+                        );
+                        let _ = std::io::stdout().flush();
+                    },
+                )?;
+                println!();
                 let elapsed = start.elapsed();
+                if arena_log_path.is_some() {
+                    println!(
+                        "{} Arena-only cards from the client's card database.",
+                        database.arena_only_cards().len()
+                    );
+                }
+                if *update {
+                    let start = std::time::Instant::now();
+                    let changelog = database.update(self.config.database_path(), *dry_run)?;
+                    Tracker::print_changelog(&changelog, self.config.verbose());
+                    println!(
+                        "[{:.2?}] Card-db in {} {}.",
+                        start.elapsed(),
+                        self.config.database_path().to_str().unwrap(),
+                        if *dry_run {
+                            "is left as it was in a dry run"
+                        } else {
+                            "has been updated"
+                        }
+                    );
+                    return Ok(());
+                }
+                if *dry_run {
+                    println!(
+                        "[{:.2?}] Scryfall database read from: {}, nothing is written in a dry run.",
+                        elapsed,
+                        scryfall_db_path.to_str().unwrap()
+                    );
+                    Tracker::print_set_report(&database, set_filter);
+                    return Ok(());
+                }
                 println!(
                     "[{:.2?}] Scryfall database read from: {}, creating card-db for the tracker... ",
                     elapsed,
-                    database_path.to_str().unwrap()
+                    scryfall_db_path.to_str().unwrap()
                 );
 
                 let start = std::time::Instant::now();
@@ -42,8 +119,688 @@ impl Tracker {
                     elapsed,
                     self.config.database_path().to_str().unwrap()
                 );
+                if self.config.verbose() {
+                    Tracker::print_set_report(&database, set_filter);
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Ingest(player_log_path, follow) => {
+                let mut log = CollectorLog::open(player_log_path);
+                let mut history = History::open(self.config.database_path())?;
+                let verbose = self.config.verbose();
+                let mut record_event = |event: EventResult| {
+                    match event {
+                        Ok(event) => {
+                            let stored = history.record(&event)?;
+                            if stored {
+                                println!("{} (stored)", event);
+                            } else if verbose {
+                                println!("{}", event);
+                            }
+                        }
+                        Err(e) => eprintln!("Skipping a malformed collector line: {}", e),
+                    }
+                    Ok(())
+                };
+
+                if *follow {
+                    println!(
+                        "Following {} for data collector events...",
+                        player_log_path.to_str().unwrap()
+                    );
+                    log.follow(Duration::from_secs(1), record_event)?;
+                } else {
+                    for event in log.read_events()? {
+                        record_event(event)?;
+                    }
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Collection(at) => {
+                let history = History::open(self.config.database_path())?;
+                match self.collection_at(&history, at)? {
+                    Some(snapshot) => {
+                        println!(
+                            "Collection of {} seen from {} to {}:",
+                            snapshot.account_id, snapshot.first_seen, snapshot.last_seen
+                        );
+                        let mut cards = snapshot.collection.iter().collect::<Vec<_>>();
+                        cards.sort_unstable();
+                        for (arena_id, count) in cards {
+                            println!("{:>8} x{}", arena_id, count);
+                        }
+                    }
+                    None => println!("No collection snapshot was stored before {}.", at),
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Acquired(arena_id) => {
+                let history = History::open(self.config.database_path())?;
+                let acquisitions = history.acquisitions(*arena_id)?;
+                if acquisitions.is_empty() {
+                    println!("Card {} has not been seen in the collection.", arena_id);
+                }
+                for acquisition in acquisitions {
+                    println!(
+                        "{} [{}] {}",
+                        acquisition.timestamp, acquisition.account_id, acquisition.source
+                    );
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Card(query) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let cards = match query {
+                    CardQuery::ArenaId(arena_id) => {
+                        if let Some(card) = database.arena_only_cards().get(arena_id) {
+                            Tracker::print_arena_only_card(card);
+                            return Ok(());
+                        }
+                        database.card(*arena_id).into_iter().collect()
+                    }
+                    CardQuery::Name(name) => database.find_by_name(name, self.config.language()),
+                    CardQuery::SetNumber(set, collector_number) => database
+                        .find_by_set_number(set, collector_number)
+                        .into_iter()
+                        .collect(),
+                    CardQuery::Text(text) => return Tracker::print_text_matches(&database, text),
+                };
+
+                if cards.is_empty() {
+                    println!("No card found.");
+                }
+                for card in cards {
+                    Tracker::print_card(card, self.localization(&database, card));
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Search(query) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let cards = database.search(query, self.config.language());
+
+                for card in &cards {
+                    let localization = self.localization(&database, card);
+                    if self.config.verbose() {
+                        Tracker::print_card(card, localization);
+                    } else {
+                        println!(
+                            "{}{} [{}] {} #{}, {}",
+                            card.name,
+                            Tracker::printed_name(localization),
+                            card.arena_id.unwrap_or_default(),
+                            card.set.to_uppercase(),
+                            card.collector_number,
+                            card.rarity
+                        );
+                    }
+                }
+                println!("{} cards found.", cards.len());
+                Ok(())
+            }
+            configuration::TrackerCommand::Unmapped(set_filter) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let history = History::open(self.config.database_path())?;
+
+                let unmapped = history
+                    .seen_arena_ids()?
+                    .into_iter()
+                    .filter(|(arena_id, _)| database.card(*arena_id).is_none())
+                    .collect::<Vec<_>>();
+                if unmapped.is_empty() {
+                    println!("Every card of the collection history is in the card database.");
+                    return Ok(());
+                }
+
+                let set_counts = database.set_counts();
+                println!(
+                    "{:>8} {:>5}  {:<30} {:<5} {:<12} Likely cause",
+                    "Arena id", "Owned", "Title", "Set", "Set status"
+                );
+                for (arena_id, count) in &unmapped {
+                    let client_card = database.client_cards().get(arena_id);
+                    let (status, cause) = match client_card {
+                        Some(card) => Tracker::unmapped_cause(card, set_filter, &set_counts),
+                        None if database.client_cards().is_empty() => (
+                            "unknown",
+                            "no client card database, run createdb with --arena-log to learn more"
+                                .to_string(),
+                        ),
+                        None => (
+                            "unknown",
+                            "not in the client's card database either, it may be newer than the log"
+                                .to_string(),
+                        ),
+                    };
+                    println!(
+                        "{:>8} {:>5}  {:<30} {:<5} {:<12} {}",
+                        arena_id,
+                        count,
+                        client_card
+                            .and_then(|card| card.title.as_deref())
+                            .unwrap_or("?"),
+                        client_card.map_or("?", |card| card.set.as_str()),
+                        status,
+                        cause
+                    );
+                }
+                println!(
+                    "{} cards are missing from the card database.",
+                    unmapped.len()
+                );
+                Ok(())
+            }
+            configuration::TrackerCommand::Bans(format) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let history = History::open(self.config.database_path())?;
+                let owned = match self.collection_at(&history, &Utc::now())? {
+                    Some(snapshot) => snapshot
+                        .collection
+                        .iter()
+                        .map(|(arena_id, _)| arena_id)
+                        .collect::<HashSet<_>>(),
+                    None => {
+                        println!(
+                            "No collection snapshot was stored yet, ingest a Player.log first."
+                        );
+                        return Ok(());
+                    }
+                };
+
+                let changes = database
+                    .legality_history(format)?
+                    .into_iter()
+                    .filter(|(_, change)| owned.contains(&change.arena_id))
+                    .collect::<Vec<_>>();
+                if changes.is_empty() {
+                    println!("None of the owned cards changed legality in {}.", format);
+                }
+                for (timestamp, change) in changes {
+                    println!(
+                        "{} {:>8} {:<30} {}",
+                        &timestamp[..10],
+                        change.arena_id,
+                        change.name,
+                        Tracker::describe_legality_change(
+                            change.old_legality.as_ref(),
+                            change.new_legality.as_ref()
+                        )
+                    );
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Prices {
+                set_filter,
+                currency,
+                since,
+                limit,
+            } => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let dates = database.price_dates()?;
+                let to = *dates
+                    .last()
+                    .ok_or("No prices were stored yet, please run createdb first")?;
+                let from = match since {
+                    Some(since) => {
+                        *dates
+                            .iter()
+                            .rev()
+                            .find(|date| *date <= since)
+                            .ok_or_else(|| {
+                                format!(
+                                "No prices were stored on or before {}, the first ones are from {}",
+                                since, dates[0]
+                            )
+                            })?
+                    }
+                    None if dates.len() > 1 => dates[dates.len() - 2],
+                    None => to,
+                };
+                if from == to {
+                    println!(
+                        "There are only the prices of {} to compare, run createdb again later to see them move.",
+                        to
+                    );
+                    return Ok(());
+                }
+
+                prices::print_movement(
+                    &database,
+                    set_filter,
+                    currency,
+                    from,
+                    to,
+                    *limit,
+                    self.config.verbose(),
+                )?;
                 Ok(())
             }
+            configuration::TrackerCommand::Value(currency, limit) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let history = History::open(self.config.database_path())?;
+                let date = *database
+                    .price_dates()?
+                    .last()
+                    .ok_or("No prices were stored yet, please run createdb first")?;
+                let collection = match self.collection_at(&history, &Utc::now())? {
+                    Some(snapshot) => snapshot.collection,
+                    None => {
+                        println!(
+                            "No collection snapshot was stored yet, ingest a Player.log first."
+                        );
+                        return Ok(());
+                    }
+                };
+
+                prices::print_value(
+                    &database,
+                    &collection,
+                    currency,
+                    date,
+                    *limit,
+                    self.config.verbose(),
+                )?;
+                Ok(())
+            }
+            configuration::TrackerCommand::Completion(set_filter, format) => {
+                let database = MtgaDb::open(self.config.database_path())?;
+                let history = History::open(self.config.database_path())?;
+                let collection = self
+                    .collection_at(&history, &Utc::now())?
+                    .map(|snapshot| snapshot.collection)
+                    .unwrap_or_default();
+
+                let completions = completion::completion(&database, set_filter, &collection);
+                match format {
+                    ReportFormat::Table => completion::print_table(&completions),
+                    ReportFormat::Json => completion::print_json(&completions)?,
+                    ReportFormat::Csv => completion::print_csv(&completions),
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::Inventory(plot) => {
+                let history = History::open(self.config.database_path())?;
+                let account_id = self.account_id(&history)?.unwrap_or_default();
+                let snapshots = match plot {
+                    Some(_) => history.inventory_snapshots(&account_id)?,
+                    None => history.latest_inventory(&account_id)?.into_iter().collect(),
+                };
+                if snapshots.is_empty() {
+                    println!("No inventory snapshot was stored yet, ingest a Player.log first.");
+                    return Ok(());
+                }
+
+                match plot {
+                    Some(series) => inventory::plot_history(&snapshots, series),
+                    None => {
+                        let database = MtgaDb::open(self.config.database_path())?;
+                        let booster_sets =
+                            inventory::booster_sets(&history.opened_boosters()?, &database);
+                        inventory::print_inventory(&snapshots[0], &booster_sets)
+                    }
+                }
+                Ok(())
+            }
+            configuration::TrackerCommand::DeckCost(decklist_path) => {
+                let deck =
+                    Tracker::read_decklist(decklist_path, DeckFormat::from_path(decklist_path))?;
+                let database = MtgaDb::open(self.config.database_path())?;
+                let history = History::open(self.config.database_path())?;
+                let collection = match self.collection_at(&history, &Utc::now())? {
+                    Some(snapshot) => snapshot.collection,
+                    None => {
+                        println!("No collection snapshot was stored yet, counting every card as missing.");
+                        Default::default()
+                    }
+                };
+
+                let cost = deck
+                    .crafting_cost(&database, &collection)
+                    .map_err(|errors| {
+                        Tracker::print_deck_errors(&errors);
+                        format!("{} cards could not be resolved", errors.0.len())
+                    })?;
+                let account_id = self.account_id(&history)?.unwrap_or_default();
+                let owned = history
+                    .latest_inventory(&account_id)?
+                    .map(|snapshot| Wildcards::owned(&snapshot.inventory));
+                deck::print_cost(&cost, owned.as_ref());
+                Ok(())
+            }
+            configuration::TrackerCommand::DeckArena(decklist_path) => {
+                let deck =
+                    Tracker::read_decklist(decklist_path, DeckFormat::from_path(decklist_path))?;
+                let database = MtgaDb::open(self.config.database_path())?;
+                let deck = Tracker::resolve_deck(&deck, &database)?;
+                print!("{}", DeckFormat::Arena.serialize(&deck));
+                Ok(())
+            }
+            configuration::TrackerCommand::DeckConvert {
+                decklist_path,
+                from,
+                to,
+            } => {
+                let deck = Tracker::read_decklist(decklist_path, *from)?;
+                let database = MtgaDb::open(self.config.database_path())?;
+                let deck = Tracker::resolve_deck(&deck, &database)?;
+                print!("{}", to.serialize(&deck));
+                Ok(())
+            }
+            configuration::TrackerCommand::ShowConfig => {
+                match self.config.config_path() {
+                    Some(path) if path.exists() => {
+                        println!("# Configuration file: {}", path.display())
+                    }
+                    Some(path) => println!("# Configuration file: {} (not found)", path.display()),
+                    None => println!("# No configuration directory on this platform"),
+                }
+                print!("{}", toml::to_string(self.config.settings())?);
+                Ok(())
+            }
+        }
+    }
+
+    fn print_card(card: &ScryCard, localization: Option<&Localization>) {
+        println!(
+            "{}{} [{}] {} #{}, {}",
+            card.name,
+            Tracker::printed_name(localization),
+            card.arena_id.unwrap_or_default(),
+            card.set.to_uppercase(),
+            card.collector_number,
+            card.rarity
+        );
+        if let Some(localization) = localization {
+            Tracker::print_card_text(
+                localization.printed_type_line.as_deref(),
+                localization.printed_text.as_deref(),
+            );
+        }
+        match &card.card_faces {
+            Some(faces) => {
+                for face in faces {
+                    println!("  {} {}", face.name, face.mana_cost);
+                    Tracker::print_card_text(
+                        face.type_line.as_deref(),
+                        face.oracle_text.as_deref(),
+                    );
+                }
+            }
+            None => {
+                if let Some(mana_cost) = &card.mana_cost {
+                    println!("  {}", mana_cost);
+                }
+                Tracker::print_card_text(card.type_line.as_deref(), card.oracle_text.as_deref());
+            }
+        }
+        println!();
+    }
+
+    /// Reads the last card database the data collector logged into the Player.log.
+    fn read_client_cards(
+        player_log_path: &std::path::Path,
+    ) -> Result<Vec<ArenaCard>, Box<dyn std::error::Error>> {
+        let mut log = CollectorLog::open(player_log_path);
+        log.read_events()?
+            .into_iter()
+            .filter_map(|event| match event {
+                Ok(CollectorEvent::CardDatabase(entry)) => Some(entry.attachment),
+                _ => None,
+            })
+            .last()
+            .ok_or_else(|| {
+                format!(
+                    "No card database in {}, is the data collector injected?",
+                    player_log_path.display()
+                )
+                .into()
+            })
+    }
+
+    fn print_changelog(changelog: &Changelog, verbose: bool) {
+        if !changelog.added.is_empty() {
+            println!("New cards:");
+            for (arena_id, name) in &changelog.added {
+                println!("  + {} [{}]", name, arena_id);
+            }
+        }
+        if !changelog.removed.is_empty() {
+            println!("Removed cards:");
+            for (arena_id, name) in &changelog.removed {
+                println!("  - {} [{}]", name, arena_id);
+            }
+        }
+        if !changelog.errata.is_empty() {
+            println!("Oracle text errata:");
+            for erratum in &changelog.errata {
+                println!("  {} [{}]", erratum.name, erratum.arena_id);
+                if verbose {
+                    for line in erratum.old_text.lines() {
+                        println!("    - {}", line);
+                    }
+                    for line in erratum.new_text.lines() {
+                        println!("    + {}", line);
+                    }
+                }
+            }
+        }
+        if !changelog.legality_changes.is_empty() {
+            println!("Legality changes:");
+            for change in &changelog.legality_changes {
+                println!(
+                    "  {} [{}] {}: {} -> {}",
+                    change.name,
+                    change.arena_id,
+                    change.format,
+                    change.old_legality.as_ref().map_or("-", |l| l.as_str()),
+                    change.new_legality.as_ref().map_or("-", |l| l.as_str())
+                );
+            }
+        }
+        println!(
+            "{} new, {} removed, {} updated cards.",
+            changelog.added.len(),
+            changelog.removed.len(),
+            changelog.updated
+        );
+    }
+
+    fn print_text_matches(
+        database: &MtgaDb,
+        query: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let found = database.find_by_text(query)?;
+        if found.is_empty() {
+            println!("No card found.");
+        }
+        for text_match in found {
+            let card = text_match.card;
+            println!(
+                "{} [{}] {} #{}, {}",
+                text_match.face_name,
+                card.arena_id.unwrap_or_default(),
+                card.set.to_uppercase(),
+                card.collector_number,
+                card.rarity
+            );
+            println!("    {}", text_match.snippet.replace('\n', " "));
+        }
+        Ok(())
+    }
+
+    /// Guesses why a card of the client's card database has no Scryfall card in the card database. Returns
+    /// whether its set is configured, and the likely cause.
+    fn unmapped_cause(
+        card: &ArenaCard,
+        set_filter: &SetFilter,
+        set_counts: &HashMap<&str, usize>,
+    ) -> (&'static str, String) {
+        let set = card.set.to_lowercase();
+        let status = if set_filter.includes(&set) {
+            "configured"
+        } else {
+            "filtered out"
+        };
+
+        let cause = if card.is_token {
+            "a token, Scryfall has no Arena ids for tokens".to_string()
+        } else if card.is_rebalanced {
+            "a rebalanced Alchemy card without an Arena id on Scryfall".to_string()
+        } else if !set_filter.includes(&set) {
+            format!("the set {} is filtered out, add it to the sets", card.set)
+        } else if !set_counts.contains_key(set.as_str()) {
+            format!(
+                "no Scryfall set with the client's set code {}, it may have a different code on Scryfall",
+                card.set
+            )
+        } else if card.is_digital_only {
+            "an Arena-only card Scryfall does not have".to_string()
+        } else {
+            "Scryfall has no card with this set and collector number".to_string()
+        };
+        (status, cause)
+    }
+
+    fn print_arena_only_card(card: &ArenaCard) {
+        println!(
+            "{} [{}] {} #{}, {}",
+            card.title.as_deref().unwrap_or("(no title)"),
+            card.grp_id,
+            card.set,
+            card.collector_number,
+            mtgadb::arena_rarity(&card.rarity)
+        );
+        println!(
+            "  Only in the client's card database{}{}",
+            if card.is_digital_only {
+                ", digital only"
+            } else {
+                ""
+            },
+            if card.is_rebalanced {
+                ", rebalanced"
+            } else {
+                ""
+            }
+        );
+        println!();
+    }
+
+    /// The texts of the card in the preferred language, if it is not English and the card database has them.
+    fn localization<'a>(&self, database: &'a MtgaDb, card: &ScryCard) -> Option<&'a Localization> {
+        database.localization(card.arena_id?, self.config.language()?)
+    }
+
+    fn printed_name(localization: Option<&Localization>) -> String {
+        match localization.and_then(|localization| localization.printed_name.as_ref()) {
+            Some(printed_name) => format!(" ({})", printed_name),
+            None => String::new(),
+        }
+    }
+
+    /// The account chosen in the configuration, or else the account seen last.
+    fn account_id(&self, history: &History) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match self.config.account_id() {
+            Some(account_id) => Ok(Some(account_id.to_string())),
+            None => history.latest_account(),
+        }
+    }
+
+    fn collection_at(
+        &self,
+        history: &History,
+        at: &DateTime<Utc>,
+    ) -> Result<Option<CollectionSnapshot>, Box<dyn std::error::Error>> {
+        match self.account_id(history)? {
+            Some(account_id) => history.collection_at(&account_id, at),
+            None => Ok(None),
+        }
+    }
+
+    fn read_decklist(path: &Path, format: DeckFormat) -> Result<Deck, Box<dyn std::error::Error>> {
+        let decklist = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        format.parse(&decklist).map_err(|errors| {
+            Tracker::print_deck_errors(&errors);
+            format!(
+                "{} lines of {} could not be read",
+                errors.0.len(),
+                path.display()
+            )
+            .into()
+        })
+    }
+
+    fn resolve_deck(deck: &Deck, database: &MtgaDb) -> Result<Deck, Box<dyn std::error::Error>> {
+        deck.resolve(database).map_err(|errors| {
+            Tracker::print_deck_errors(&errors);
+            format!("{} cards could not be resolved", errors.0.len()).into()
+        })
+    }
+
+    fn print_deck_errors(errors: &DeckErrors) {
+        for error in &errors.0 {
+            eprintln!("{}", error);
+        }
+    }
+
+    fn print_card_text(type_line: Option<&str>, oracle_text: Option<&str>) {
+        if let Some(type_line) = type_line {
+            println!("  {}", type_line);
+        }
+        for line in oracle_text.unwrap_or_default().lines() {
+            println!("    {}", line);
+        }
+    }
+
+    fn print_set_report(database: &MtgaDb, set_filter: &SetFilter) {
+        let set_counts = database.set_counts();
+
+        let mut sets = set_counts.iter().collect::<Vec<_>>();
+        sets.sort_unstable();
+        println!("Sets with Arena cards in the card-db:");
+        for (set, count) in sets {
+            println!("{:>10} {:>6} cards", set, count);
+        }
+
+        if let SetFilter::Only(listed_sets) = set_filter {
+            let empty_sets = listed_sets
+                .iter()
+                .filter(|set| !set_counts.contains_key(set.as_str()))
+                .map(|set| set.as_str())
+                .collect::<Vec<_>>();
+            if !empty_sets.is_empty() {
+                println!(
+                    "Listed sets without any Arena cards in the Scryfall database: {}",
+                    empty_sets.join(", ")
+                );
+            }
         }
+
+        let mut filtered_out_sets = database.filtered_out_sets().iter().collect::<Vec<_>>();
+        filtered_out_sets.sort_unstable();
+        if !filtered_out_sets.is_empty() {
+            println!("Sets with Arena cards that are not listed:");
+            for (set, count) in filtered_out_sets {
+                println!("{:>10} {:>6} cards", set, count);
+            }
+        }
+    }
+
+    fn describe_legality_change(old: Option<&Legality>, new: Option<&Legality>) -> String {
+        let describe = match (old, new) {
+            (_, Some(Legality::Banned)) => "banned",
+            (Some(Legality::Banned), _) => "unbanned",
+            (_, Some(Legality::Restricted)) => "restricted",
+            (Some(Legality::Restricted), _) => "unrestricted",
+            (Some(Legality::Legal), _) => "rotated out",
+            (_, Some(Legality::Legal)) => "became legal",
+            _ => "changed",
+        };
+        format!(
+            "{} ({} -> {})",
+            describe,
+            old.map_or("unlisted", Legality::as_str),
+            new.map_or("unlisted", Legality::as_str)
+        )
     }
 }
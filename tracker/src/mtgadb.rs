@@ -1,81 +1,1624 @@
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs::File;
-use std::io::Read;
 use std::path::Path;
-mod model;
+mod bulk;
+mod mana;
+pub mod model;
+mod search;
 
-use rusqlite::{params, Connection};
-use serde_json;
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, Transaction};
+use serde::de::DeserializeOwned;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 
-use model::ScryCard;
+use crate::collector::model::ArenaCard;
+use crate::database;
+pub use bulk::BulkProgress;
+use mana::{ManaCost, Pips};
+use model::{Legality, Rarity, ScryCard, ScryCardFace};
+pub use search::Query;
 
 pub struct MtgaDb {
     arena_cards: HashMap<u32, ScryCard>,
+    arena_only_cards: HashMap<u32, ArenaCard>,
+    client_cards: HashMap<u32, ArenaCard>,
+    localizations: HashMap<u32, HashMap<String, Localization>>,
+    index: CardIndex,
+    filtered_out_sets: HashMap<String, usize>,
+    db: Option<Connection>,
+}
+
+/// The Arena ids of the cards by the keys decklists name the cards by, built with the card database.
+struct CardIndex {
+    mtgo_ids: HashMap<u32, u32>,
+    /// Keyed by the lowercase set code and collector number.
+    set_numbers: HashMap<(String, String), u32>,
+    /// Keyed by the folded full name and front face name, ordered by set and collector number.
+    names: HashMap<String, Vec<u32>>,
+}
+
+/// The texts printed on a non-English printing of a card. The texts of the faces of a multi-face card are
+/// joined the way Scryfall joins the English ones.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Localization {
+    pub printed_name: Option<String>,
+    pub printed_text: Option<String>,
+    pub printed_type_line: Option<String>,
+}
+
+/// A card face found by a full-text search.
+pub struct TextMatch<'a> {
+    pub card: &'a ScryCard,
+    pub face_name: String,
+    /// The best matching part of the texts, with the matches between `[` and `]`.
+    pub snippet: String,
+}
+
+/// Decides which sets of the Scryfall database end up in the card database. Cards without an Arena id are
+/// always left out.
+pub enum SetFilter {
+    /// Every set that has at least one card with an Arena id.
+    AllArenaSets,
+    /// Only the listed sets.
+    Only(Vec<String>),
+}
+
+impl SetFilter {
+    pub fn only<I, S>(sets: I) -> SetFilter
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        SetFilter::Only(
+            sets.into_iter()
+                .map(|set| set.as_ref().to_lowercase())
+                .collect(),
+        )
+    }
+
+    pub fn includes(&self, set: &str) -> bool {
+        match self {
+            SetFilter::AllArenaSets => true,
+            SetFilter::Only(sets) => sets.iter().any(|s| s == set),
+        }
+    }
 }
 
 impl MtgaDb {
-    pub fn create_from_scryfall_db<P>(
+    /// Reads the Arena cards of a Scryfall bulk data file. The client's card database, if there is one, fills
+    /// in the cards Scryfall has no Arena id for: a Scryfall card with the same set and collector number gets
+    /// the Arena id, and the cards without any Scryfall card are kept as Arena-only cards.
+    ///
+    /// The English printing is the card of an Arena id, a printing in another language is only kept if there
+    /// is no English one. The texts of the printings in the other languages are kept as localizations.
+    pub fn create_from_scryfall_db<P, F>(
         scryfall_db_path: P,
-        required_sets: &[&str],
+        set_filter: &SetFilter,
+        client_cards: Vec<ArenaCard>,
+        on_progress: F,
     ) -> Result<MtgaDb, Box<dyn Error>>
     where
         P: AsRef<Path>,
+        F: FnMut(&BulkProgress),
     {
-        let mut arena_cards = HashMap::new();
+        let mut arena_cards: HashMap<u32, ScryCard> = HashMap::new();
+        let mut localizations = HashMap::new();
+        let mut filtered_out_sets = HashMap::new();
 
-        let required_sets = required_sets
-            .into_iter()
-            .map(|&set| set.to_lowercase())
-            .collect::<Vec<String>>();
+        // the rebalanced cards share the collector number of the original card
+        let client_numbers = client_cards
+            .iter()
+            .filter(|card| !card.is_rebalanced)
+            .map(|card| {
+                (
+                    (card.set.to_lowercase(), card.collector_number.clone()),
+                    card.grp_id,
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let mut scryfall_arena_ids = HashSet::new();
+        let mut matched_cards = Vec::new();
 
-        let mut file = File::open(scryfall_db_path)?;
+        bulk::read_cards(
+            scryfall_db_path,
+            |mut card| {
+                let arena_id = match card.arena_id {
+                    Some(arena_id) => arena_id,
+                    None => {
+                        let number = (card.set.clone(), card.collector_number.clone());
+                        if let Some(&arena_id) = client_numbers.get(&number) {
+                            card.arena_id = Some(arena_id);
+                            matched_cards.push(card);
+                        }
+                        return false;
+                    }
+                };
+                scryfall_arena_ids.insert(arena_id);
 
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
+                if !set_filter.includes(&card.set) {
+                    *filtered_out_sets.entry(card.set).or_insert(0) += 1;
+                    return false;
+                }
+                add_localization(&mut localizations, arena_id, &card);
+                match arena_cards.entry(arena_id) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(card);
+                        true
+                    }
+                    Entry::Occupied(mut entry) if card.lang == "en" && entry.get().lang != "en" => {
+                        entry.insert(card);
+                        false
+                    }
+                    Entry::Occupied(_) => false,
+                }
+            },
+            on_progress,
+        )?;
 
-        let result: Vec<ScryCard> = serde_json::from_slice(&data)?;
+        // a card Scryfall has the Arena id for wins over one matched by its collector number, and of the
+        // matched ones, the English printing wins
+        matched_cards.sort_by_key(|card| card.lang != "en");
+        let mut matched_arena_ids = HashSet::new();
+        for card in matched_cards {
+            let arena_id = card.arena_id.unwrap_or_default();
+            if scryfall_arena_ids.contains(&arena_id) {
+                continue;
+            }
+            if set_filter.includes(&card.set) {
+                add_localization(&mut localizations, arena_id, &card);
+            }
+            if matched_arena_ids.insert(arena_id) {
+                if set_filter.includes(&card.set) {
+                    arena_cards.insert(arena_id, card);
+                } else {
+                    *filtered_out_sets.entry(card.set).or_insert(0) += 1;
+                }
+            }
+        }
+
+        scryfall_arena_ids.extend(matched_arena_ids);
+        localizations.retain(|arena_id, _| arena_cards.contains_key(arena_id));
 
-        for card in result {
-            if required_sets.iter().any(|s| *s == card.set) && card.arena_id.is_some() {
-                arena_cards.entry(card.arena_id.unwrap()).or_insert(card);
+        let mut arena_only_cards = HashMap::new();
+        for card in &client_cards {
+            let set = card.set.to_lowercase();
+            if scryfall_arena_ids.contains(&card.grp_id) {
+                continue;
             }
+            if set_filter.includes(&set) {
+                arena_only_cards.insert(card.grp_id, card.clone());
+            } else {
+                *filtered_out_sets.entry(set).or_insert(0) += 1;
+            }
+        }
+
+        Ok(MtgaDb {
+            index: CardIndex::new(&arena_cards),
+            arena_cards,
+            arena_only_cards,
+            client_cards: client_cards
+                .into_iter()
+                .map(|card| (card.grp_id, card))
+                .collect(),
+            localizations,
+            filtered_out_sets,
+            db: None,
+        })
+    }
+
+    /// Reads back the card database exported into the tracker database.
+    pub fn open<P>(path: P) -> Result<MtgaDb, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let db = database::open(path)?;
+        let arena_cards = load_cards(&db, "cards_db")?;
+        let arena_only_cards = load_cards(&db, "arena_only_cards")?;
+        let client_cards = load_cards(&db, "client_cards")?;
+        let localizations = load_localizations(&db)?;
+
+        Ok(MtgaDb {
+            index: CardIndex::new(&arena_cards),
+            arena_cards,
+            arena_only_cards,
+            client_cards,
+            localizations,
+            filtered_out_sets: HashMap::new(),
+            db: Some(db),
+        })
+    }
+
+    /// A card database of the given cards, kept in memory only.
+    #[cfg(test)]
+    pub fn from_cards(cards: Vec<ScryCard>, client_cards: Vec<ArenaCard>) -> MtgaDb {
+        let arena_cards = cards
+            .into_iter()
+            .map(|card| (card.arena_id.unwrap_or_default(), card))
+            .collect();
+        MtgaDb {
+            index: CardIndex::new(&arena_cards),
+            arena_cards,
+            arena_only_cards: HashMap::new(),
+            client_cards: client_cards
+                .into_iter()
+                .map(|card| (card.grp_id, card))
+                .collect(),
+            localizations: HashMap::new(),
+            filtered_out_sets: HashMap::new(),
+            db: None,
         }
-        Ok(MtgaDb { arena_cards })
     }
 
     pub fn arena_cards(&self) -> &HashMap<u32, ScryCard> {
         &self.arena_cards
     }
 
+    pub fn card(&self, arena_id: u32) -> Option<&ScryCard> {
+        self.arena_cards.get(&arena_id)
+    }
+
+    /// Finds the cards by name, ignoring case and diacritics. Both the full name and the names of the faces
+    /// are searched, so `Petty Theft` finds `Brazen Borrower // Petty Theft`. Exact matches come first, then
+    /// the names starting with the query, then the ones containing it. With a language, the names printed in
+    /// that language are searched too.
+    pub fn find_by_name(&self, query: &str, language: Option<&str>) -> Vec<&ScryCard> {
+        let query = fold_name(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut found = self
+            .arena_cards
+            .values()
+            .filter_map(|card| {
+                let mut names = vec![fold_name(&card.name)];
+                if let Some(faces) = &card.card_faces {
+                    names.extend(faces.iter().map(|face| fold_name(&face.name)));
+                }
+                let printed_name = language
+                    .and_then(|language| self.localization(card.arena_id?, language))
+                    .and_then(|localization| localization.printed_name.as_deref());
+                if let Some(printed_name) = printed_name {
+                    names.push(fold_name(printed_name));
+                    names.extend(printed_name.split(" // ").map(fold_name));
+                }
+
+                let rank = names
+                    .iter()
+                    .filter_map(|name| {
+                        if *name == query {
+                            Some(0)
+                        } else if name.starts_with(&query) {
+                            Some(1)
+                        } else if name.contains(&query) {
+                            Some(2)
+                        } else {
+                            None
+                        }
+                    })
+                    .min()?;
+                Some((rank, card))
+            })
+            .collect::<Vec<_>>();
+
+        found.sort_by(|(rank_a, card_a), (rank_b, card_b)| {
+            rank_a
+                .cmp(rank_b)
+                .then_with(|| card_a.name.cmp(&card_b.name))
+                .then_with(|| card_a.set.cmp(&card_b.set))
+                .then_with(|| card_a.collector_number.cmp(&card_b.collector_number))
+        });
+        found.into_iter().map(|(_, card)| card).collect()
+    }
+
+    /// Finds the printings of a card by its exact name, ignoring case and diacritics, ordered by set and
+    /// collector number. Like in decklists, a multi-face card is found by its full name or the name of its
+    /// front face.
+    pub fn find_by_exact_name(&self, name: &str) -> Vec<&ScryCard> {
+        self.index
+            .names
+            .get(&fold_name(name))
+            .into_iter()
+            .flatten()
+            .filter_map(|arena_id| self.card(*arena_id))
+            .collect()
+    }
+
+    /// Finds the printing with the MTGO id, of the regular or the foil version of the card.
+    pub fn find_by_mtgo_id(&self, mtgo_id: u32) -> Option<&ScryCard> {
+        self.card(*self.index.mtgo_ids.get(&mtgo_id)?)
+    }
+
+    /// Finds a printing by its set code and collector number, ignoring the case of both.
+    pub fn find_by_set_number(&self, set: &str, collector_number: &str) -> Option<&ScryCard> {
+        let key = (set.to_lowercase(), collector_number.to_lowercase());
+        self.card(*self.index.set_numbers.get(&key)?)
+    }
+
+    /// The texts of the card printed in the language, if the card database has them. English has none, the
+    /// card itself is English.
+    pub fn localization(&self, arena_id: u32, language: &str) -> Option<&Localization> {
+        self.localizations.get(&arena_id)?.get(language)
+    }
+
+    /// The cards of the client's card database that have no Scryfall card, keyed by their Arena id.
+    pub fn arena_only_cards(&self) -> &HashMap<u32, ArenaCard> {
+        &self.arena_only_cards
+    }
+
+    /// The client's card database the card database was created with, keyed by the Arena id. Empty if it was
+    /// created from the Scryfall data only.
+    pub fn client_cards(&self) -> &HashMap<u32, ArenaCard> {
+        &self.client_cards
+    }
+
+    /// Searches the names, type lines, Oracle and flavor texts of the card faces with an
+    /// [FTS5 query](https://www.sqlite.org/fts5.html#full_text_query_syntax), like `"draw a card"` or
+    /// `flying NOT flash`. Diacritics are ignored. The best matches come first, names weighing the most.
+    pub fn find_by_text(&self, query: &str) -> Result<Vec<TextMatch<'_>>, Box<dyn Error>> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or("The full-text index is only in an exported card database")?;
+
+        let mut statement = db.prepare(
+            "SELECT arena_id, name, snippet(cards_fts, -1, '[', ']', '...', 16) FROM cards_fts
+            WHERE cards_fts MATCH ?1
+            ORDER BY bm25(cards_fts, 0.0, 0.0, 10.0, 2.0, 1.0, 0.5)",
+        )?;
+        let mut rows = statement.query([query])?;
+
+        let mut found = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| format!("Could not search for {}: {}", query, e))?
+        {
+            let arena_id: u32 = row.get(0)?;
+            if let Some(card) = self.card(arena_id) {
+                found.push(TextMatch {
+                    card,
+                    face_name: row.get(1)?,
+                    snippet: row.get(2)?,
+                });
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns the legality changes recorded in the format when the card database was refreshed, with the time
+    /// of the refresh, in chronological order.
+    pub fn legality_history(
+        &self,
+        format: &str,
+    ) -> Result<Vec<(String, LegalityChange)>, Box<dyn Error>> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or("The legality history is only in an exported card database")?;
+
+        let mut statement = db.prepare(
+            "SELECT timestamp, arena_id, name, old_legality, new_legality FROM legality_history
+            WHERE format = ?1 ORDER BY timestamp, name, arena_id",
+        )?;
+        let history = statement
+            .query_map([format], |row| {
+                Ok((
+                    row.get(0)?,
+                    LegalityChange {
+                        arena_id: row.get(1)?,
+                        name: row.get(2)?,
+                        format: format.to_string(),
+                        old_legality: row.get::<_, Option<String>>(3)?.map(Legality::from),
+                        new_legality: row.get::<_, Option<String>>(4)?.map(Legality::from),
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(history)
+    }
+
+    /// The days the price history has prices from, in chronological order.
+    pub fn price_dates(&self) -> Result<Vec<NaiveDate>, Box<dyn Error>> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or("The price history is only in an exported card database")?;
+
+        let mut statement = db.prepare("SELECT DISTINCT date FROM price_history ORDER BY date")?;
+        let dates = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|date| Ok(date?.parse()?))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(dates)
+    }
+
+    /// The prices of the cards in the currency on the day. A card is missing if it was not in the card
+    /// database built that day, and its price is `None` if Scryfall had no price for it.
+    pub fn prices_on(
+        &self,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<HashMap<u32, Option<f64>>, Box<dyn Error>> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or("The price history is only in an exported card database")?;
+
+        let mut statement = db.prepare(
+            "SELECT arena_id, price FROM price_history WHERE date = ?1 AND currency = ?2",
+        )?;
+        let prices = statement
+            .query_map(params![date.to_string(), currency], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(prices)
+    }
+
+    /// Finds the cards matching a search query, ordered by name, set and collector number. With a language,
+    /// the names are also searched in the names printed in that language.
+    pub fn search(&self, query: &Query, language: Option<&str>) -> Vec<&ScryCard> {
+        let mut found = self
+            .arena_cards
+            .values()
+            .filter(|card| {
+                let printed_name = language
+                    .and_then(|language| self.localization(card.arena_id?, language))
+                    .and_then(|localization| localization.printed_name.as_deref());
+                query.matches(card, printed_name)
+            })
+            .collect::<Vec<_>>();
+
+        found.sort_by(|card_a, card_b| {
+            card_a
+                .name
+                .cmp(&card_b.name)
+                .then_with(|| card_a.set.cmp(&card_b.set))
+                .then_with(|| card_a.collector_number.cmp(&card_b.collector_number))
+        });
+        found
+    }
+
+    /// The number of Arena cards in the database per set.
+    pub fn set_counts(&self) -> HashMap<&str, usize> {
+        let mut set_counts = HashMap::new();
+        for card in self.arena_cards.values() {
+            *set_counts.entry(card.set.as_str()).or_insert(0) += 1;
+        }
+        set_counts
+    }
+
+    /// The sets that had Arena cards in the Scryfall database but were left out by the set filter, with the
+    /// number of Arena card printings they had.
+    pub fn filtered_out_sets(&self) -> &HashMap<String, usize> {
+        &self.filtered_out_sets
+    }
+
+    /// Writes the card database into the tracker database, replacing the one written earlier. The legality
+    /// changes since the earlier one are kept in the legality history, and the prices in the price history.
     pub fn export<P>(&self, path: P) -> Result<(), Box<dyn Error>>
     where
         P: AsRef<Path>,
     {
-        let mut db = Connection::open(path)?;
+        let mut db = database::open(path)?;
         let tx = db.transaction()?;
+        let old_cards: HashMap<u32, ScryCard> = load_cards(&tx, "cards_db")?;
 
-        tx.execute("DROP TABLE IF EXISTS cards_db", [])?;
-        tx.execute(
-            "CREATE TABLE IF NOT EXISTS cards_db (
-            'arena_id' INTEGER PRIMARY KEY NOT NULL,
-            'set' TEXT NOT NULL,
-            'rarity' TEXT NOT NULL,
-            'booster' INTEGER NOT NULL,
-            'data' BLOB NOT NULL
-        )",
-            [],
-        )?;
-
-        tx.execute("CREATE INDEX set_idx ON cards_db('set')", [])?;
-        tx.execute("CREATE INDEX rarity_idx ON cards_db('rarity')", [])?;
+        let mut changes = Vec::new();
+        for (arena_id, card) in self.arena_cards() {
+            if let Some(old_card) = old_cards.get(arena_id) {
+                changes.extend(legality_changes(*arena_id, old_card, card));
+            }
+        }
+        record_legality_changes(&tx, &changes)?;
+        record_prices(&tx, self.arena_cards())?;
 
+        tx.execute("DELETE FROM cards_fts", [])?;
+        for table in CARD_TABLES {
+            tx.execute(&format!("DELETE FROM {}", table), [])?;
+        }
         for (arena_id, card) in self.arena_cards() {
-            tx.execute("INSERT INTO cards_db ('arena_id', 'set', 'rarity', 'booster', 'data') VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![arena_id, card.set, card.rarity, card.booster as i32, serde_json::to_value(card)?])?;
+            insert_card(&tx, *arena_id, card)?;
         }
+        self.replace_localizations(&tx)?;
+        self.replace_client_cards(&tx)?;
 
         tx.commit()?;
         Ok(())
     }
+
+    /// Updates the card database written by an earlier `export` to this one, touching only the cards that
+    /// changed: a card changes if its Scryfall card is a different one, or if the Scryfall data changed apart
+    /// from the fields Scryfall changes every day, like the prices and the ranks. An unchanged card keeps the
+    /// old values of those in its stored data, the prices of the day are in the price history. The client's
+    /// card database is only replaced if this one has it. Everything happens in one transaction, which is
+    /// rolled back in a dry run. The legality changes and the prices are kept in the histories.
+    pub fn update<P>(&self, path: P, dry_run: bool) -> Result<Changelog, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut db = database::open(path)?;
+        let tx = db.transaction()?;
+        let old_cards: HashMap<u32, ScryCard> = load_cards(&tx, "cards_db")?;
+
+        let mut changelog = Changelog::default();
+        for (arena_id, old_card) in &old_cards {
+            if !self.arena_cards.contains_key(arena_id) {
+                delete_card(&tx, *arena_id)?;
+                changelog.removed.push((*arena_id, old_card.name.clone()));
+            }
+        }
+
+        for (arena_id, card) in &self.arena_cards {
+            match old_cards.get(arena_id) {
+                None => changelog.added.push((*arena_id, card.name.clone())),
+                Some(old_card)
+                    if old_card.id == card.id && stable_data(old_card)? == stable_data(card)? =>
+                {
+                    continue
+                }
+                Some(old_card) => {
+                    if oracle_texts(old_card) != oracle_texts(card) {
+                        changelog.errata.push(Erratum {
+                            arena_id: *arena_id,
+                            name: card.name.clone(),
+                            old_text: oracle_texts(old_card),
+                            new_text: oracle_texts(card),
+                        });
+                    }
+
+                    changelog
+                        .legality_changes
+                        .extend(legality_changes(*arena_id, old_card, card));
+                    changelog.updated += 1;
+                    delete_card(&tx, *arena_id)?;
+                }
+            }
+            insert_card(&tx, *arena_id, card)?;
+        }
+
+        self.replace_localizations(&tx)?;
+        if !self.client_cards.is_empty() {
+            self.replace_client_cards(&tx)?;
+        }
+        record_legality_changes(&tx, &changelog.legality_changes)?;
+        record_prices(&tx, &self.arena_cards)?;
+
+        changelog.added.sort_unstable();
+        changelog.removed.sort_unstable();
+        changelog.errata.sort_by_key(|erratum| erratum.arena_id);
+        if !dry_run {
+            tx.commit()?;
+        }
+        Ok(changelog)
+    }
+
+    fn replace_localizations(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
+        tx.execute("DELETE FROM card_localizations", [])?;
+        for (arena_id, localizations) in &self.localizations {
+            for (language, localization) in localizations {
+                tx.execute(
+                    "INSERT INTO card_localizations ('arena_id', 'lang', 'printed_name', 'printed_text', 'printed_type_line') VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        arena_id,
+                        language,
+                        localization.printed_name,
+                        localization.printed_text,
+                        localization.printed_type_line
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn replace_client_cards(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
+        tx.execute("DELETE FROM arena_only_cards", [])?;
+        for (arena_id, card) in self.arena_only_cards() {
+            tx.execute("INSERT INTO arena_only_cards ('arena_id', 'title', 'set', 'rarity', 'collector_number', 'digital_only', 'rebalanced', 'data') VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![arena_id, card.title, card.set.to_lowercase(), arena_rarity(&card.rarity).as_str(), card.collector_number, card.is_digital_only as i32, card.is_rebalanced as i32, serde_json::to_value(card)?])?;
+        }
+
+        tx.execute("DELETE FROM client_cards", [])?;
+        for (arena_id, card) in self.client_cards() {
+            tx.execute(
+                "INSERT INTO client_cards ('arena_id', 'title', 'set', 'data') VALUES (?1, ?2, ?3, ?4)",
+                params![arena_id, card.title, card.set, serde_json::to_value(card)?],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl CardIndex {
+    fn new(arena_cards: &HashMap<u32, ScryCard>) -> CardIndex {
+        // in the order of the name lookups, which also makes the lowest Arena id win an MTGO id two
+        // printings share
+        let mut cards = arena_cards.iter().collect::<Vec<_>>();
+        cards.sort_by(|(arena_id_a, a), (arena_id_b, b)| {
+            a.set
+                .cmp(&b.set)
+                .then_with(|| a.collector_number.cmp(&b.collector_number))
+                .then_with(|| arena_id_a.cmp(arena_id_b))
+        });
+
+        let mut index = CardIndex {
+            mtgo_ids: HashMap::new(),
+            set_numbers: HashMap::new(),
+            names: HashMap::new(),
+        };
+        for (&arena_id, card) in cards {
+            for mtgo_id in card.mtgo_id.iter().chain(&card.mtgo_foil_id) {
+                index.mtgo_ids.entry(*mtgo_id).or_insert(arena_id);
+            }
+            index
+                .set_numbers
+                .entry((
+                    card.set.to_lowercase(),
+                    card.collector_number.to_lowercase(),
+                ))
+                .or_insert(arena_id);
+
+            let name = fold_name(&card.name);
+            let front_face = card
+                .card_faces
+                .as_ref()
+                .and_then(|faces| faces.first())
+                .map(|face| fold_name(&face.name))
+                .filter(|front_face| *front_face != name);
+            for name in [Some(name), front_face].into_iter().flatten() {
+                index.names.entry(name).or_default().push(arena_id);
+            }
+        }
+        index
+    }
+}
+
+/// The tables holding the Scryfall cards, `cards_db` last, as the others refer to it. The full-text index
+/// `cards_fts` is keyed by rowid instead of Arena id, see [`FTS_ROWS_PER_CARD`].
+const CARD_TABLES: [&str; 6] = [
+    "card_faces",
+    "card_colors",
+    "card_keywords",
+    "card_legalities",
+    "card_parts",
+    "cards_db",
+];
+
+/// The rowid of a card face in `cards_fts` is `arena_id * FTS_ROWS_PER_CARD + face_index`, so the faces of a
+/// card are a rowid range, which FTS5 finds without scanning the index. No card has this many faces.
+const FTS_ROWS_PER_CARD: i64 = 16;
+
+/// The fields of the Scryfall data, on the card and its faces, that change every day without the card
+/// changing.
+const VOLATILE_FIELDS: [&str; 6] = [
+    "prices",
+    "edhrec_rank",
+    "penny_rank",
+    "image_uris",
+    "image_status",
+    "highres_image",
+];
+
+/// What an update of the card database changed.
+#[derive(Default)]
+pub struct Changelog {
+    /// The Arena ids and names of the new cards.
+    pub added: Vec<(u32, String)>,
+    /// The Arena ids and names of the cards no longer in the card database.
+    pub removed: Vec<(u32, String)>,
+    pub errata: Vec<Erratum>,
+    pub legality_changes: Vec<LegalityChange>,
+    /// The number of cards that changed in any way, errata and legality changes included.
+    pub updated: usize,
+}
+
+/// A change in the Oracle text of a card, the texts of the faces are separated by `//`.
+pub struct Erratum {
+    pub arena_id: u32,
+    pub name: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// A change in the legality of a card in a format. A legality is missing if Scryfall did not list the
+/// format for the card.
+pub struct LegalityChange {
+    pub arena_id: u32,
+    pub name: String,
+    pub format: String,
+    pub old_legality: Option<Legality>,
+    pub new_legality: Option<Legality>,
+}
+
+/// The formats the legality of the card changed in, in alphabetical order.
+fn legality_changes(arena_id: u32, old_card: &ScryCard, card: &ScryCard) -> Vec<LegalityChange> {
+    let mut formats = old_card
+        .legalities
+        .keys()
+        .chain(card.legalities.keys())
+        .collect::<Vec<_>>();
+    formats.sort();
+    formats.dedup();
+
+    formats
+        .into_iter()
+        .filter(|format| old_card.legalities.get(*format) != card.legalities.get(*format))
+        .map(|format| LegalityChange {
+            arena_id,
+            name: card.name.clone(),
+            format: format.clone(),
+            old_legality: old_card.legalities.get(format).cloned(),
+            new_legality: card.legalities.get(format).cloned(),
+        })
+        .collect()
+}
+
+fn record_legality_changes(
+    tx: &Transaction,
+    changes: &[LegalityChange],
+) -> Result<(), Box<dyn Error>> {
+    let timestamp = database::timestamp(&Utc::now());
+    for change in changes {
+        tx.execute(
+            "INSERT INTO legality_history ('arena_id', 'name', 'format', 'old_legality', 'new_legality', 'timestamp') VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                change.arena_id,
+                change.name,
+                change.format,
+                change.old_legality.as_ref().map(Legality::as_str),
+                change.new_legality.as_ref().map(Legality::as_str),
+                timestamp
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Keeps the printed texts of a non-English card.
+fn add_localization(
+    localizations: &mut HashMap<u32, HashMap<String, Localization>>,
+    arena_id: u32,
+    card: &ScryCard,
+) {
+    if card.lang == "en" {
+        return;
+    }
+
+    // the printed texts of multi-face cards are only on the faces
+    let joined = |separator, text: fn(&ScryCardFace) -> Option<&String>| {
+        let faces = card.card_faces.as_ref()?;
+        let texts = faces.iter().map(text).collect::<Option<Vec<_>>>()?;
+        Some(
+            texts
+                .iter()
+                .map(|text| text.as_str())
+                .collect::<Vec<_>>()
+                .join(separator),
+        )
+    };
+    let localization = Localization {
+        printed_name: card
+            .printed_name
+            .clone()
+            .or_else(|| joined(" // ", |face| face.printed_name.as_ref())),
+        printed_text: card
+            .printed_text
+            .clone()
+            .or_else(|| joined("\n//\n", |face| face.printed_text.as_ref())),
+        printed_type_line: card
+            .printed_type_line
+            .clone()
+            .or_else(|| joined(" // ", |face| face.printed_type_line.as_ref())),
+    };
+    if localization != Localization::default() {
+        localizations
+            .entry(arena_id)
+            .or_default()
+            .entry(card.lang.clone())
+            .or_insert(localization);
+    }
+}
+
+fn load_localizations(
+    db: &Connection,
+) -> Result<HashMap<u32, HashMap<String, Localization>>, Box<dyn Error>> {
+    let mut statement = db.prepare(
+        "SELECT arena_id, lang, printed_name, printed_text, printed_type_line FROM card_localizations",
+    )?;
+    let mut rows = statement.query([])?;
+
+    let mut localizations: HashMap<u32, HashMap<String, Localization>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        localizations.entry(row.get(0)?).or_default().insert(
+            row.get(1)?,
+            Localization {
+                printed_name: row.get(2)?,
+                printed_text: row.get(3)?,
+                printed_type_line: row.get(4)?,
+            },
+        );
+    }
+    Ok(localizations)
+}
+
+/// Stores today's prices of the cards, replacing the ones stored earlier today. Every currency Scryfall lists
+/// gets a row, with NULL for the prices Scryfall has no value for or lists in a form that is not a number.
+fn record_prices(tx: &Transaction, cards: &HashMap<u32, ScryCard>) -> Result<(), Box<dyn Error>> {
+    let date = Utc::now().naive_utc().date().to_string();
+    tx.execute("DELETE FROM price_history WHERE date = ?1", [&date])?;
+
+    for (arena_id, card) in cards {
+        for (currency, price) in &card.prices {
+            let price = price.as_deref().and_then(|price| price.parse::<f64>().ok());
+            tx.execute(
+                "INSERT INTO price_history ('arena_id', 'date', 'currency', 'price') VALUES (?1, ?2, ?3, ?4)",
+                params![arena_id, date, currency, price],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a card into `cards_db`, the relational tables and the full-text index.
+fn insert_card(tx: &Transaction, arena_id: u32, card: &ScryCard) -> Result<(), Box<dyn Error>> {
+    // the pips of all faces, so both halves of a split card count
+    let pips = card
+        .mana_costs()
+        .map_err(|e| format!("{}: {}", card.name, e))?
+        .iter()
+        .map(ManaCost::pips)
+        .sum::<Pips>();
+
+    tx.execute("INSERT INTO cards_db ('arena_id', 'set', 'rarity', 'booster', 'data', 'pips_w', 'pips_u', 'pips_b', 'pips_r', 'pips_g') VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    params![arena_id, card.set, card.rarity.as_str(), card.booster as i32, serde_json::to_value(card)?, pips.white, pips.blue, pips.black, pips.red, pips.green])?;
+    export_card_tables(tx, arena_id, card)?;
+
+    tx.execute("INSERT INTO cards_fts (rowid, arena_id, face_index, name, type_line, oracle_text, flavor_text)
+        SELECT arena_id * ?2 + face_index, arena_id, face_index, name, type_line, oracle_text, flavor_text FROM card_faces WHERE arena_id = ?1",
+    params![arena_id, FTS_ROWS_PER_CARD])?;
+    Ok(())
+}
+
+fn delete_card(tx: &Transaction, arena_id: u32) -> Result<(), Box<dyn Error>> {
+    let first_rowid = arena_id as i64 * FTS_ROWS_PER_CARD;
+    tx.execute(
+        "DELETE FROM cards_fts WHERE rowid BETWEEN ?1 AND ?2",
+        params![first_rowid, first_rowid + FTS_ROWS_PER_CARD - 1],
+    )?;
+    for table in CARD_TABLES {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE arena_id = ?1", table),
+            params![arena_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// The Scryfall data of the card compared by `update`, without the [`VOLATILE_FIELDS`].
+fn stable_data(card: &ScryCard) -> Result<serde_json::Value, Box<dyn Error>> {
+    let mut data = serde_json::to_value(card)?;
+    let faces = data
+        .get_mut("card_faces")
+        .and_then(serde_json::Value::as_array_mut);
+    for face in faces.into_iter().flatten() {
+        if let Some(face) = face.as_object_mut() {
+            for field in VOLATILE_FIELDS {
+                face.remove(field);
+            }
+        }
+    }
+    if let Some(card) = data.as_object_mut() {
+        for field in VOLATILE_FIELDS {
+            card.remove(field);
+        }
+    }
+    Ok(data)
+}
+
+/// The Oracle text of the card, or the texts of its faces.
+fn oracle_texts(card: &ScryCard) -> String {
+    match &card.card_faces {
+        Some(faces) => faces
+            .iter()
+            .map(|face| face.oracle_text.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n//\n"),
+        None => card.oracle_text.clone().unwrap_or_default(),
+    }
+}
+
+/// Reads the cards stored as JSON in the `data` column of a card table, keyed by their Arena id.
+fn load_cards<T>(db: &Connection, table: &str) -> Result<HashMap<u32, T>, Box<dyn Error>>
+where
+    T: DeserializeOwned,
+{
+    let mut statement = db.prepare(&format!("SELECT arena_id, data FROM {}", table))?;
+    let mut rows = statement.query([])?;
+
+    let mut cards = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let arena_id: u32 = row.get(0)?;
+        let data: String = row.get(1)?;
+        cards.insert(arena_id, serde_json::from_str(&data)?);
+    }
+    Ok(cards)
+}
+
+/// Writes the card into the relational tables next to `cards_db`.
+fn export_card_tables(
+    tx: &Transaction,
+    arena_id: u32,
+    card: &ScryCard,
+) -> Result<(), Box<dyn Error>> {
+    match &card.card_faces {
+        Some(faces) => {
+            for (face_index, face) in faces.iter().enumerate() {
+                tx.execute("INSERT INTO card_faces ('arena_id', 'face_index', 'name', 'mana_cost', 'type_line', 'oracle_text', 'power', 'toughness', 'loyalty', 'flavor_text') VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![arena_id, face_index, face.name, face.mana_cost, face.type_line, face.oracle_text, face.power, face.toughness, face.loyalty, face.flavor_text])?;
+            }
+        }
+        None => {
+            tx.execute("INSERT INTO card_faces ('arena_id', 'face_index', 'name', 'mana_cost', 'type_line', 'oracle_text', 'power', 'toughness', 'loyalty', 'flavor_text') VALUES (?1, 0, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![arena_id, card.name, card.mana_cost, card.type_line, card.oracle_text, card.power, card.toughness, card.loyalty, card.flavor_text])?;
+        }
+    }
+
+    // the colors of a multi-face card are only given on its faces
+    let mut colors: Vec<_> = match (&card.colors, &card.card_faces) {
+        (None, Some(faces)) => faces
+            .iter()
+            .filter_map(|face| face.colors.as_ref())
+            .flat_map(|colors| colors.iter())
+            .collect(),
+        (colors, _) => colors.iter().flat_map(|colors| colors.iter()).collect(),
+    };
+    colors.sort();
+    colors.dedup();
+    let color_kinds = [
+        ("colors", colors),
+        ("color_identity", card.color_identity.iter().collect()),
+        (
+            "produced_mana",
+            card.produced_mana
+                .iter()
+                .flat_map(|colors| colors.iter())
+                .collect(),
+        ),
+    ];
+    for (kind, colors) in color_kinds {
+        for color in colors {
+            tx.execute("INSERT OR IGNORE INTO card_colors ('arena_id', 'kind', 'color') VALUES (?1, ?2, ?3)",
+            params![arena_id, kind, color.as_str()])?;
+        }
+    }
+
+    for keyword in &card.keywords {
+        tx.execute(
+            "INSERT OR IGNORE INTO card_keywords ('arena_id', 'keyword') VALUES (?1, ?2)",
+            params![arena_id, keyword],
+        )?;
+    }
+
+    for (format, legality) in &card.legalities {
+        tx.execute(
+            "INSERT INTO card_legalities ('arena_id', 'format', 'legality') VALUES (?1, ?2, ?3)",
+            params![arena_id, format, legality.as_str()],
+        )?;
+    }
+
+    for part in card.all_parts.iter().flatten() {
+        tx.execute("INSERT OR IGNORE INTO card_parts ('arena_id', 'scryfall_id', 'component', 'name', 'type_line') VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![arena_id, part.id.to_string(), part.component, part.name, part.type_line])?;
+    }
+
+    Ok(())
+}
+
+/// Converts the name of a `CardRarity` of the client. Basic lands have their own rarity in the client, they
+/// are commons for Scryfall.
+pub fn arena_rarity(rarity: &str) -> Rarity {
+    match rarity {
+        "Land" | "Common" => Rarity::Common,
+        "Uncommon" => Rarity::Uncommon,
+        "Rare" => Rarity::Rare,
+        "MythicRare" => Rarity::Mythic,
+        _ => Rarity::Unknown(rarity.to_string()),
+    }
+}
+
+/// Folds a card name for comparison: lowercase, without diacritics, with the `æ` ligature spelled out as
+/// Scryfall does in the current Oracle names.
+fn fold_name(name: &str) -> String {
+    name.trim()
+        .nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .replace('æ', "ae")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{card, TempFile};
+
+    #[test]
+    fn records_the_prices_of_the_day() {
+        let file = TempFile::new("prices.db");
+        let cards = vec![
+            card(json!({ "prices": { "usd": "0.29", "eur": "n/a", "tix": null } })),
+            card(json!({ "arena_id": 70006, "name": "Plains", "prices": { "usd": null } })),
+        ];
+        MtgaDb::from_cards(cards, vec![]).export(&file.0).unwrap();
+
+        let database = MtgaDb::open(&file.0).unwrap();
+        let today = Utc::now().naive_utc().date();
+        assert_eq!(database.price_dates().unwrap(), [today]);
+        assert_eq!(
+            database.prices_on("usd", today).unwrap(),
+            HashMap::from([(70002, Some(0.29)), (70006, None)])
+        );
+        assert_eq!(
+            database.prices_on("eur", today).unwrap(),
+            HashMap::from([(70002, None)])
+        );
+        assert!(database
+            .prices_on("usd", today.pred_opt().unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn has_no_price_history_in_memory() {
+        let database = MtgaDb::from_cards(vec![card(json!({}))], vec![]);
+        assert!(database.price_dates().is_err());
+        assert!(database
+            .prices_on("usd", Utc::now().naive_utc().date())
+            .is_err());
+    }
+
+    /// The cards of a first export: one to change, one to remove and one whose prices alone change.
+    fn old_cards() -> Vec<ScryCard> {
+        vec![
+            card(json!({ "legalities": { "standard": "not_legal" } })),
+            card(json!({
+                "arena_id": 70004,
+                "name": "Brazen Borrower",
+                "oracle_text": "Flash. Flying.",
+            })),
+            card(json!({ "arena_id": 70006, "name": "Plains", "oracle_text": "({T}: Add {W}.)" })),
+        ]
+    }
+
+    fn new_cards() -> Vec<ScryCard> {
+        vec![
+            card(json!({
+                "oracle_text": "Lightning Strike deals 4 damage to any target.",
+                "legalities": { "standard": "legal" },
+            })),
+            card(json!({
+                "arena_id": 70006,
+                "name": "Plains",
+                "oracle_text": "({T}: Add {W}.)",
+                "prices": { "usd": "0.10" },
+            })),
+            card(json!({
+                "arena_id": 70008,
+                "name": "Shock",
+                "oracle_text": "Shock deals 2 damage to any target.",
+            })),
+        ]
+    }
+
+    /// The Arena ids of the cards the full-text index finds.
+    fn full_text_search(path: &Path, query: &str) -> Vec<u32> {
+        let db = Connection::open(path).unwrap();
+        let mut statement = db
+            .prepare("SELECT arena_id FROM cards_fts WHERE cards_fts MATCH ?1 ORDER BY rowid")
+            .unwrap();
+        let ids = statement
+            .query_map([query], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        ids
+    }
+
+    #[test]
+    fn update_lists_the_added_changed_and_removed_cards() {
+        let file = TempFile::new("update.db");
+        MtgaDb::from_cards(old_cards(), vec![])
+            .export(&file.0)
+            .unwrap();
+
+        let changelog = MtgaDb::from_cards(new_cards(), vec![])
+            .update(&file.0, false)
+            .unwrap();
+        assert_eq!(changelog.added, [(70008, "Shock".to_string())]);
+        assert_eq!(changelog.removed, [(70004, "Brazen Borrower".to_string())]);
+        assert_eq!(
+            changelog
+                .errata
+                .iter()
+                .map(|erratum| (
+                    erratum.arena_id,
+                    erratum.old_text.as_str(),
+                    erratum.new_text.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            [(
+                70002,
+                "Lightning Strike deals 3 damage to any target.",
+                "Lightning Strike deals 4 damage to any target."
+            )]
+        );
+        assert_eq!(
+            changelog
+                .legality_changes
+                .iter()
+                .map(|change| (
+                    change.arena_id,
+                    change.format.as_str(),
+                    change.old_legality.clone(),
+                    change.new_legality.clone()
+                ))
+                .collect::<Vec<_>>(),
+            [(
+                70002,
+                "standard",
+                Some(Legality::NotLegal),
+                Some(Legality::Legal)
+            )]
+        );
+        assert_eq!(changelog.updated, 1);
+
+        let database = MtgaDb::open(&file.0).unwrap();
+        let mut arena_ids = database.arena_cards().keys().copied().collect::<Vec<_>>();
+        arena_ids.sort_unstable();
+        assert_eq!(arena_ids, [70002, 70006, 70008]);
+        assert_eq!(
+            database.card(70002).unwrap().oracle_text.as_deref(),
+            Some("Lightning Strike deals 4 damage to any target.")
+        );
+    }
+
+    #[test]
+    fn update_keeps_the_full_text_index_in_step() {
+        let file = TempFile::new("update-fts.db");
+        MtgaDb::from_cards(old_cards(), vec![])
+            .export(&file.0)
+            .unwrap();
+        assert_eq!(full_text_search(&file.0, "borrower"), [70004]);
+
+        MtgaDb::from_cards(new_cards(), vec![])
+            .update(&file.0, false)
+            .unwrap();
+        assert!(full_text_search(&file.0, "borrower").is_empty());
+        assert!(full_text_search(&file.0, "\"deals 3\"").is_empty());
+        assert_eq!(full_text_search(&file.0, "\"deals 4\""), [70002]);
+        assert_eq!(full_text_search(&file.0, "shock"), [70008]);
+        assert_eq!(full_text_search(&file.0, "plains"), [70006]);
+    }
+
+    #[test]
+    fn dry_run_leaves_the_database_unchanged() {
+        let file = TempFile::new("update-dry-run.db");
+        MtgaDb::from_cards(old_cards(), vec![])
+            .export(&file.0)
+            .unwrap();
+
+        let changelog = MtgaDb::from_cards(new_cards(), vec![])
+            .update(&file.0, true)
+            .unwrap();
+        assert_eq!(changelog.added.len(), 1);
+        assert_eq!(changelog.removed.len(), 1);
+        assert_eq!(changelog.updated, 1);
+
+        let database = MtgaDb::open(&file.0).unwrap();
+        let mut arena_ids = database.arena_cards().keys().copied().collect::<Vec<_>>();
+        arena_ids.sort_unstable();
+        assert_eq!(arena_ids, [70002, 70004, 70006]);
+        assert_eq!(
+            database.card(70002).unwrap().oracle_text.as_deref(),
+            Some("Lightning Strike deals 3 damage to any target.")
+        );
+        assert_eq!(full_text_search(&file.0, "borrower"), [70004]);
+    }
+
+    #[test]
+    fn second_update_changes_nothing() {
+        let file = TempFile::new("update-twice.db");
+        MtgaDb::from_cards(old_cards(), vec![])
+            .export(&file.0)
+            .unwrap();
+        let database = MtgaDb::from_cards(new_cards(), vec![]);
+        database.update(&file.0, false).unwrap();
+
+        let changelog = database.update(&file.0, false).unwrap();
+        assert!(changelog.added.is_empty());
+        assert!(changelog.removed.is_empty());
+        assert!(changelog.errata.is_empty());
+        assert!(changelog.legality_changes.is_empty());
+        assert_eq!(changelog.updated, 0);
+    }
+
+    fn fixture_database() -> MtgaDb {
+        MtgaDb::create_from_scryfall_db(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/scryfall.json"),
+            &SetFilter::AllArenaSets,
+            vec![],
+            |_| {},
+        )
+        .unwrap()
+    }
+
+    fn names(cards: Vec<&ScryCard>) -> Vec<&str> {
+        cards.into_iter().map(|card| card.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_cards_by_name_ignoring_case_and_diacritics() {
+        let database = MtgaDb::from_cards(
+            vec![
+                card(json!({ "arena_id": 1, "name": "Lim-Dûl's Vault" })),
+                card(json!({ "arena_id": 2, "name": "Æther Vial" })),
+                card(json!({ "arena_id": 3, "name": "Vault of Whispers" })),
+                card(json!({ "arena_id": 4, "name": "Vault Skirge" })),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            names(database.find_by_name("LIM-DUL'S VAULT", None)),
+            ["Lim-Dûl's Vault"]
+        );
+        assert_eq!(names(database.find_by_name("aether", None)), ["Æther Vial"]);
+        assert_eq!(
+            names(database.find_by_name("vault of whispers", None)),
+            ["Vault of Whispers"]
+        );
+        // the names starting with the query before the ones containing it
+        assert_eq!(
+            names(database.find_by_name("vault", None)),
+            ["Vault Skirge", "Vault of Whispers", "Lim-Dûl's Vault"]
+        );
+        assert!(database.find_by_name(" ", None).is_empty());
+        assert_eq!(
+            names(database.find_by_exact_name("lim-dûl's vault")),
+            ["Lim-Dûl's Vault"]
+        );
+        assert!(database.find_by_exact_name("Vault").is_empty());
+    }
+
+    #[test]
+    fn finds_multi_face_cards_by_their_faces() {
+        let database = fixture_database();
+        assert_eq!(
+            names(database.find_by_name("petty theft", None)),
+            ["Brazen Borrower // Petty Theft"]
+        );
+        assert_eq!(
+            names(database.find_by_exact_name("Brazen Borrower")),
+            ["Brazen Borrower // Petty Theft"]
+        );
+        assert_eq!(
+            names(database.find_by_exact_name("brazen borrower // petty theft")),
+            ["Brazen Borrower // Petty Theft"]
+        );
+        assert!(database.find_by_exact_name("Petty Theft").is_empty());
+    }
+
+    #[test]
+    fn finds_cards_by_id_and_printing() {
+        let database = fixture_database();
+        assert_eq!(database.card(70006).unwrap().name, "Plains");
+        assert!(database.card(1).is_none());
+        assert_eq!(
+            database.find_by_set_number("ELD", "39").unwrap().name,
+            "Brazen Borrower // Petty Theft"
+        );
+        assert!(database.find_by_set_number("eld", "40").is_none());
+        assert_eq!(
+            database.find_by_mtgo_id(68212).unwrap().name,
+            "Lightning Strike"
+        );
+    }
+
+    /// The rows of a query for a single text column.
+    fn query(path: &Path, sql: &str) -> Vec<String> {
+        let db = Connection::open(path).unwrap();
+        let mut statement = db.prepare(sql).unwrap();
+        let rows = statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        rows
+    }
+
+    #[test]
+    fn exports_the_cards_into_relational_tables() {
+        let file = TempFile::new("relational.db");
+        fixture_database().export(&file.0).unwrap();
+
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT arena_id || ' ' || face_index || ' ' || name || ' ' || IFNULL(mana_cost, '-')
+                FROM card_faces ORDER BY arena_id, face_index"
+            ),
+            [
+                "70002 0 Lightning Strike {1}{R}",
+                "70004 0 Brazen Borrower {1}{U}{U}",
+                "70004 1 Petty Theft {1}{U}",
+                "70006 0 Plains "
+            ]
+        );
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT arena_id || ' ' || kind || ' ' || color FROM card_colors ORDER BY arena_id, kind"
+            ),
+            [
+                "70002 color_identity R",
+                "70002 colors R",
+                "70004 color_identity U",
+                "70004 colors U",
+                "70006 color_identity W"
+            ]
+        );
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT arena_id || ' ' || keyword FROM card_keywords ORDER BY arena_id, keyword"
+            ),
+            ["70004 Flash", "70004 Flying"]
+        );
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT legality FROM card_legalities WHERE arena_id = 70002 AND format IN ('modern', 'standard')
+                ORDER BY format"
+            ),
+            ["legal", "not_legal"]
+        );
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT arena_id || ' ' || component || ' ' || name FROM card_parts"
+            ),
+            ["70004 combo_piece Brazen Borrower // Petty Theft"]
+        );
+        // the whole card is still in the data column
+        assert_eq!(
+            query(
+                &file.0,
+                "SELECT json_extract(data, '$.name') FROM cards_db ORDER BY arena_id"
+            ),
+            [
+                "Lightning Strike",
+                "Brazen Borrower // Petty Theft",
+                "Plains"
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_card_faces_by_their_texts() {
+        let file = TempFile::new("full-text.db");
+        fixture_database().export(&file.0).unwrap();
+        let database = MtgaDb::open(&file.0).unwrap();
+
+        let found = database.find_by_text("flying").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].card.arena_id, Some(70004));
+        assert_eq!(found[0].face_name, "Brazen Borrower");
+        assert!(found[0].snippet.contains("[Flying]"));
+
+        let found = database.find_by_text("\"nonland permanent\"").unwrap();
+        assert_eq!(found[0].face_name, "Petty Theft");
+        assert!(database.find_by_text("\"nonland").is_err());
+    }
+
+    #[test]
+    fn ranks_the_names_over_the_other_texts() {
+        let file = TempFile::new("full-text-rank.db");
+        let cards = vec![
+            card(json!({
+                "arena_id": 1,
+                "name": "Shock",
+                "oracle_text": "Shock deals 2 damage to any target.",
+                "flavor_text": "A goblin's favorite.",
+            })),
+            card(json!({ "arena_id": 2, "name": "Goblin Guide", "oracle_text": "Haste" })),
+            card(
+                json!({ "arena_id": 3, "name": "Séance", "oracle_text": "Exile target creature card." }),
+            ),
+        ];
+        MtgaDb::from_cards(cards, vec![]).export(&file.0).unwrap();
+        let database = MtgaDb::open(&file.0).unwrap();
+
+        let found = database.find_by_text("goblin").unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|found| found.face_name.as_str())
+                .collect::<Vec<_>>(),
+            ["Goblin Guide", "Shock"]
+        );
+        assert_eq!(database.find_by_text("seance").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn has_no_full_text_index_in_memory() {
+        assert!(fixture_database().find_by_text("flying").is_err());
+    }
+
+    fn strike(legalities: serde_json::Value) -> Vec<ScryCard> {
+        vec![card(json!({ "legalities": legalities }))]
+    }
+
+    /// The old and new legalities of the changes recorded in the format, oldest first.
+    fn legality_history(path: &Path, format: &str) -> Vec<(Option<Legality>, Option<Legality>)> {
+        MtgaDb::open(path)
+            .unwrap()
+            .legality_history(format)
+            .unwrap()
+            .into_iter()
+            .map(|(_, change)| {
+                assert_eq!(change.arena_id, 70002);
+                assert_eq!(change.name, "Lightning Strike");
+                (change.old_legality, change.new_legality)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_the_legality_changes_of_each_refresh() {
+        let file = TempFile::new("legality-history.db");
+        MtgaDb::from_cards(
+            strike(json!({ "standard": "legal", "historic": "legal" })),
+            vec![],
+        )
+        .export(&file.0)
+        .unwrap();
+        assert!(legality_history(&file.0, "standard").is_empty());
+
+        MtgaDb::from_cards(
+            strike(json!({ "standard": "banned", "pioneer": "legal" })),
+            vec![],
+        )
+        .export(&file.0)
+        .unwrap();
+        assert_eq!(
+            legality_history(&file.0, "standard"),
+            [(Some(Legality::Legal), Some(Legality::Banned))]
+        );
+        assert_eq!(
+            legality_history(&file.0, "historic"),
+            [(Some(Legality::Legal), None)]
+        );
+        assert_eq!(
+            legality_history(&file.0, "pioneer"),
+            [(None, Some(Legality::Legal))]
+        );
+
+        let unbanned = MtgaDb::from_cards(
+            strike(json!({ "standard": "legal", "pioneer": "legal" })),
+            vec![],
+        );
+        unbanned.update(&file.0, true).unwrap();
+        assert_eq!(legality_history(&file.0, "standard").len(), 1);
+        unbanned.update(&file.0, false).unwrap();
+        assert_eq!(
+            legality_history(&file.0, "standard"),
+            [
+                (Some(Legality::Legal), Some(Legality::Banned)),
+                (Some(Legality::Banned), Some(Legality::Legal))
+            ]
+        );
+        assert_eq!(legality_history(&file.0, "pioneer").len(), 1);
+    }
+
+    /// A card database read from a Scryfall bulk data file of the cards.
+    fn bulk_database(name: &str, cards: Vec<ScryCard>) -> MtgaDb {
+        let file = TempFile::new(name);
+        fs::write(&file.0, serde_json::to_string(&cards).unwrap()).unwrap();
+        MtgaDb::create_from_scryfall_db(&file.0, &SetFilter::AllArenaSets, vec![], |_| {}).unwrap()
+    }
+
+    fn localized_database() -> MtgaDb {
+        bulk_database(
+            "localized.json",
+            vec![
+                card(json!({ "lang": "de", "printed_name": "Blitzschlag" })),
+                card(json!({})),
+                card(json!({ "lang": "fr", "printed_name": "Frappe de foudre" })),
+                card(json!({
+                    "arena_id": 70004,
+                    "name": "Brazen Borrower // Petty Theft",
+                    "lang": "ja",
+                    "card_faces": [
+                        { "object": "card_face", "name": "Brazen Borrower", "mana_cost": "{1}{U}{U}",
+                            "printed_name": "ずうずうしい借り手" },
+                        { "object": "card_face", "name": "Petty Theft", "mana_cost": "{1}{U}",
+                            "printed_name": "些細な盗み" },
+                    ],
+                })),
+            ],
+        )
+    }
+
+    #[test]
+    fn keeps_the_english_printing_and_the_printed_names() {
+        let database = localized_database();
+        let strike = database.card(70002).unwrap();
+        assert_eq!(strike.lang, "en");
+        assert_eq!(strike.printed_name, None);
+        // without an English printing, another language has to do
+        assert_eq!(database.card(70004).unwrap().lang, "ja");
+
+        let printed_name = |arena_id, language| {
+            database
+                .localization(arena_id, language)
+                .and_then(|localization| localization.printed_name.clone())
+        };
+        assert_eq!(printed_name(70002, "de").as_deref(), Some("Blitzschlag"));
+        assert_eq!(
+            printed_name(70002, "fr").as_deref(),
+            Some("Frappe de foudre")
+        );
+        assert_eq!(printed_name(70002, "en"), None);
+        assert_eq!(
+            printed_name(70004, "ja").as_deref(),
+            Some("ずうずうしい借り手 // 些細な盗み")
+        );
+    }
+
+    #[test]
+    fn finds_the_cards_by_the_names_printed_in_the_language() {
+        let database = localized_database();
+        assert_eq!(
+            names(database.find_by_name("blitzschlag", Some("de"))),
+            ["Lightning Strike"]
+        );
+        assert!(database.find_by_name("blitzschlag", Some("fr")).is_empty());
+        assert!(database.find_by_name("blitzschlag", None).is_empty());
+        assert_eq!(
+            names(database.find_by_name("些細な盗み", Some("ja"))),
+            ["Brazen Borrower // Petty Theft"]
+        );
+
+        let query: Query = "blitz".parse().unwrap();
+        assert_eq!(
+            names(database.search(&query, Some("de"))),
+            ["Lightning Strike"]
+        );
+        assert!(database.search(&query, None).is_empty());
+    }
+
+    #[test]
+    fn exports_the_localizations() {
+        let file = TempFile::new("localized.db");
+        localized_database().export(&file.0).unwrap();
+
+        let database = MtgaDb::open(&file.0).unwrap();
+        assert_eq!(
+            database
+                .localization(70002, "de")
+                .unwrap()
+                .printed_name
+                .as_deref(),
+            Some("Blitzschlag")
+        );
+        assert_eq!(
+            names(database.find_by_name("frappe", Some("fr"))),
+            ["Lightning Strike"]
+        );
+    }
 }
@@ -0,0 +1,115 @@
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::de::{Deserializer, SeqAccess, Visitor};
+
+use super::model::ScryCard;
+
+const PROGRESS_INTERVAL: usize = 5000;
+
+/// How far the reading of a Scryfall bulk data file got.
+pub struct BulkProgress {
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub cards_read: usize,
+    pub cards_kept: usize,
+}
+
+/// Reads a Scryfall bulk data file one card at a time, so only the cards the caller decides to keep stay in
+/// memory. `on_card` gets every card in the file and returns whether it kept it, `on_progress` is called
+/// periodically while reading and once at the end.
+pub fn read_cards<P, F, G>(path: P, on_card: F, on_progress: G) -> Result<(), Box<dyn Error>>
+where
+    P: AsRef<Path>,
+    F: FnMut(ScryCard) -> bool,
+    G: FnMut(&BulkProgress),
+{
+    let file = File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    let bytes_read = Rc::new(Cell::new(0));
+
+    let reader = CountingReader {
+        inner: BufReader::new(file),
+        bytes_read: Rc::clone(&bytes_read),
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer.deserialize_seq(CardVisitor {
+        on_card,
+        on_progress,
+        bytes_read,
+        total_bytes,
+    })?;
+    deserializer.end()?;
+
+    Ok(())
+}
+
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl<R> Read for CountingReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+struct CardVisitor<F, G> {
+    on_card: F,
+    on_progress: G,
+    bytes_read: Rc<Cell<u64>>,
+    total_bytes: u64,
+}
+
+impl<'de, F, G> Visitor<'de> for CardVisitor<F, G>
+where
+    F: FnMut(ScryCard) -> bool,
+    G: FnMut(&BulkProgress),
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of Scryfall card objects")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut progress = BulkProgress {
+            bytes_read: 0,
+            total_bytes: self.total_bytes,
+            cards_read: 0,
+            cards_kept: 0,
+        };
+        let mut next_progress = PROGRESS_INTERVAL;
+
+        while let Some(card) = seq.next_element::<ScryCard>()? {
+            progress.cards_read += 1;
+            if (self.on_card)(card) {
+                progress.cards_kept += 1;
+            }
+
+            if progress.cards_read == next_progress {
+                next_progress += PROGRESS_INTERVAL;
+                progress.bytes_read = self.bytes_read.get();
+                (self.on_progress)(&progress);
+            }
+        }
+
+        progress.bytes_read = self.bytes_read.get();
+        (self.on_progress)(&progress);
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::error::Error;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::Add;
+use std::str::FromStr;
+
+use super::model::{Color, ScryCard};
+
+/// A parsed mana cost, like `{2}{W/U}{W/U}{X}`.
+///
+/// An empty cost has no symbols, which is different from a cost of `{0}`. Symbols the parser does not know
+/// are kept as `ManaSymbol::Other`, so every cost written by Scryfall parses and prints back unchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ManaCost {
+    symbols: Vec<ManaSymbol>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ManaSymbol {
+    /// `{0}`, `{1}`, `{2}`...
+    Generic(u32),
+    /// `{X}`, `{Y}` or `{Z}`.
+    Variable(char),
+    /// `{W}`, `{U}`, `{B}`, `{R}` or `{G}`.
+    Colored(Color),
+    /// `{C}`, which has to be paid with colorless mana.
+    Colorless,
+    /// `{S}`, which has to be paid with mana from a snow source.
+    Snow,
+    /// `{W/U}`, the two colors in the order Scryfall writes them.
+    Hybrid(Color, Color),
+    /// `{2/W}`.
+    GenericHybrid(Color),
+    /// `{W/P}`.
+    Phyrexian(Color),
+    /// `{W/U/P}`.
+    PhyrexianHybrid(Color, Color),
+    /// Any other symbol, without the braces.
+    Other(String),
+}
+
+/// The number of colored mana symbols per color. A hybrid symbol counts for both of its colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pips {
+    pub white: u32,
+    pub blue: u32,
+    pub black: u32,
+    pub red: u32,
+    pub green: u32,
+}
+
+impl ManaCost {
+    pub fn symbols(&self) -> &[ManaSymbol] {
+        &self.symbols
+    }
+
+    pub fn pips(&self) -> Pips {
+        let mut pips = Pips::default();
+        for color in self.symbols.iter().flat_map(ManaSymbol::colors) {
+            match color {
+                Color::White => pips.white += 1,
+                Color::Blue => pips.blue += 1,
+                Color::Black => pips.black += 1,
+                Color::Red => pips.red += 1,
+                Color::Green => pips.green += 1,
+                _ => {}
+            }
+        }
+        pips
+    }
+
+    /// The devotion of this cost to the colors: the number of its symbols that have at least one of them.
+    pub fn devotion(&self, colors: &[Color]) -> u32 {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.colors().iter().any(|color| colors.contains(color)))
+            .count() as u32
+    }
+}
+
+impl ManaSymbol {
+    pub fn colors(&self) -> Vec<Color> {
+        match self {
+            ManaSymbol::Colored(color)
+            | ManaSymbol::GenericHybrid(color)
+            | ManaSymbol::Phyrexian(color) => vec![color.clone()],
+            ManaSymbol::Hybrid(first, second) | ManaSymbol::PhyrexianHybrid(first, second) => {
+                vec![first.clone(), second.clone()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Parses the text of a symbol between the braces.
+    fn parse(text: &str) -> ManaSymbol {
+        let upper = text.to_uppercase();
+        let parts = upper.split('/').collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            [generic] if !generic.is_empty() && generic.chars().all(|c| c.is_ascii_digit()) => {
+                match generic.parse() {
+                    Ok(generic) => ManaSymbol::Generic(generic),
+                    Err(_) => ManaSymbol::Other(text.to_string()),
+                }
+            }
+            ["X"] => ManaSymbol::Variable('X'),
+            ["Y"] => ManaSymbol::Variable('Y'),
+            ["Z"] => ManaSymbol::Variable('Z'),
+            ["C"] => ManaSymbol::Colorless,
+            ["S"] => ManaSymbol::Snow,
+            [color] => match parse_color(color) {
+                Some(color) => ManaSymbol::Colored(color),
+                None => ManaSymbol::Other(text.to_string()),
+            },
+            ["2", color] => match parse_color(color) {
+                Some(color) => ManaSymbol::GenericHybrid(color),
+                None => ManaSymbol::Other(text.to_string()),
+            },
+            [color, "P"] => match parse_color(color) {
+                Some(color) => ManaSymbol::Phyrexian(color),
+                None => ManaSymbol::Other(text.to_string()),
+            },
+            [first, second] => match hybrid_colors(first, second) {
+                Some((first, second)) => ManaSymbol::Hybrid(first, second),
+                None => ManaSymbol::Other(text.to_string()),
+            },
+            [first, second, "P"] => match hybrid_colors(first, second) {
+                Some((first, second)) => ManaSymbol::PhyrexianHybrid(first, second),
+                None => ManaSymbol::Other(text.to_string()),
+            },
+            _ => ManaSymbol::Other(text.to_string()),
+        }
+    }
+}
+
+impl FromStr for ManaCost {
+    type Err = Box<dyn Error>;
+
+    fn from_str(cost: &str) -> Result<ManaCost, Self::Err> {
+        let mut symbols = Vec::new();
+        let mut rest = cost.trim();
+        while !rest.is_empty() {
+            let symbol = rest
+                .strip_prefix('{')
+                .and_then(|rest| rest.split_once('}'))
+                .ok_or_else(|| format!("Not a mana cost: {}", cost))?;
+            symbols.push(ManaSymbol::parse(symbol.0));
+            rest = symbol.1;
+        }
+        Ok(ManaCost { symbols })
+    }
+}
+
+impl fmt::Display for ManaCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for symbol in &self.symbols {
+            write!(f, "{}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ManaSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManaSymbol::Generic(generic) => write!(f, "{{{}}}", generic),
+            ManaSymbol::Variable(variable) => write!(f, "{{{}}}", variable),
+            ManaSymbol::Colored(color) => write!(f, "{{{}}}", color),
+            ManaSymbol::Colorless => write!(f, "{{C}}"),
+            ManaSymbol::Snow => write!(f, "{{S}}"),
+            ManaSymbol::Hybrid(first, second) => write!(f, "{{{}/{}}}", first, second),
+            ManaSymbol::GenericHybrid(color) => write!(f, "{{2/{}}}", color),
+            ManaSymbol::Phyrexian(color) => write!(f, "{{{}/P}}", color),
+            ManaSymbol::PhyrexianHybrid(first, second) => {
+                write!(f, "{{{}/{}/P}}", first, second)
+            }
+            ManaSymbol::Other(text) => write!(f, "{{{}}}", text),
+        }
+    }
+}
+
+impl Add for Pips {
+    type Output = Pips;
+
+    fn add(self, other: Pips) -> Pips {
+        Pips {
+            white: self.white + other.white,
+            blue: self.blue + other.blue,
+            black: self.black + other.black,
+            red: self.red + other.red,
+            green: self.green + other.green,
+        }
+    }
+}
+
+impl Sum for Pips {
+    fn sum<I>(iter: I) -> Pips
+    where
+        I: Iterator<Item = Pips>,
+    {
+        iter.fold(Pips::default(), Add::add)
+    }
+}
+
+impl ScryCard {
+    /// The parsed mana costs of the card, one per face for multi-face cards. Scryfall joins the costs of the
+    /// faces with ` // ` in the card's own `mana_cost`, so the faces are parsed instead.
+    pub fn mana_costs(&self) -> Result<Vec<ManaCost>, Box<dyn Error>> {
+        match &self.card_faces {
+            Some(faces) => faces.iter().map(|face| face.mana_cost.parse()).collect(),
+            None => self.mana_cost.iter().map(|cost| cost.parse()).collect(),
+        }
+    }
+}
+
+const WUBRG: [Color; 5] = [
+    Color::White,
+    Color::Blue,
+    Color::Black,
+    Color::Red,
+    Color::Green,
+];
+
+fn parse_color(letter: &str) -> Option<Color> {
+    WUBRG.into_iter().find(|color| color.as_str() == letter)
+}
+
+/// Parses the colors of a hybrid symbol and puts them in Scryfall's order, which goes around the color pie:
+/// `{W/U}`, `{G/W}`, `{W/B}` and `{R/W}`, never `{U/W}`.
+fn hybrid_colors(first: &str, second: &str) -> Option<(Color, Color)> {
+    let position = |letter| WUBRG.iter().position(|color| color.as_str() == letter);
+    let (first, second) = (position(first)?, position(second)?);
+    match (second + 5 - first) % 5 {
+        1 | 2 => Some((WUBRG[first].clone(), WUBRG[second].clone())),
+        3 | 4 => Some((WUBRG[second].clone(), WUBRG[first].clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(text: &str) -> ManaCost {
+        text.parse().expect("the cost should parse")
+    }
+
+    #[test]
+    fn prints_every_kind_of_symbol_back_unchanged() {
+        for text in [
+            "",
+            "{0}",
+            "{15}",
+            "{X}{R}",
+            "{X}{Y}{Z}",
+            "{2}{W/U}{W/U}",
+            "{2/W}{2/U}",
+            "{G/P}{W/U/P}",
+            "{C}{S}",
+            "{HW}{∞}",
+        ] {
+            assert_eq!(cost(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parses_the_symbols() {
+        assert_eq!(
+            cost("{3}{X}{W/U}{2/B}{R/P}{G/W/P}{C}{S}").symbols(),
+            [
+                ManaSymbol::Generic(3),
+                ManaSymbol::Variable('X'),
+                ManaSymbol::Hybrid(Color::White, Color::Blue),
+                ManaSymbol::GenericHybrid(Color::Black),
+                ManaSymbol::Phyrexian(Color::Red),
+                ManaSymbol::PhyrexianHybrid(Color::Green, Color::White),
+                ManaSymbol::Colorless,
+                ManaSymbol::Snow,
+            ]
+        );
+        assert!(cost("").symbols().is_empty());
+        assert_ne!(cost(""), cost("{0}"));
+    }
+
+    #[test]
+    fn puts_hybrid_colors_in_scryfall_order() {
+        assert_eq!(cost("{u/w}").to_string(), "{W/U}");
+        assert_eq!(cost("{W/G}").to_string(), "{G/W}");
+        assert_eq!(cost("{B/W}").to_string(), "{W/B}");
+        assert_eq!(cost("{U/G/P}").to_string(), "{G/U/P}");
+    }
+
+    #[test]
+    fn rejects_text_that_is_not_a_cost() {
+        assert!("2R".parse::<ManaCost>().is_err());
+        assert!("{2}{R".parse::<ManaCost>().is_err());
+        assert!("{2} // {R}".parse::<ManaCost>().is_err());
+    }
+
+    #[test]
+    fn counts_the_pips_of_each_color() {
+        assert_eq!(cost("{X}{10}{C}").pips(), Pips::default());
+        assert_eq!(
+            cost("{1}{R}{R}{G}").pips(),
+            Pips {
+                red: 2,
+                green: 1,
+                ..Pips::default()
+            }
+        );
+        // hybrid symbols count for both of their colors, the generic part of {2/W} for none
+        assert_eq!(
+            cost("{W/U}{W/U}{2/B}{R/P}{G/W/P}").pips(),
+            Pips {
+                white: 3,
+                blue: 2,
+                black: 1,
+                red: 1,
+                green: 1,
+            }
+        );
+        assert_eq!(
+            [cost("{W}"), cost("{W}{U}")]
+                .iter()
+                .map(ManaCost::pips)
+                .sum::<Pips>(),
+            Pips {
+                white: 2,
+                blue: 1,
+                ..Pips::default()
+            }
+        );
+    }
+
+    #[test]
+    fn counts_a_hybrid_symbol_once_for_devotion() {
+        let cost = cost("{X}{2}{R}{R/G}{R/P}{2/R}{U}");
+        assert_eq!(cost.devotion(&[Color::Red]), 4);
+        assert_eq!(cost.devotion(&[Color::Green]), 1);
+        assert_eq!(cost.devotion(&[Color::Red, Color::Green]), 4);
+        assert_eq!(cost.devotion(&[Color::Red, Color::Blue]), 5);
+        assert_eq!(cost.devotion(&[Color::White]), 0);
+        assert_eq!(cost.devotion(&[]), 0);
+    }
+}
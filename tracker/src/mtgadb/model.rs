@@ -1,6 +1,9 @@
 use std::collections::HashMap;
+use std::fmt;
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// # Card Objects
 /// Card objects represent individual Magic: The Gathering cards that players could obtain and add to their collection
@@ -152,7 +155,7 @@ pub struct ScryCard {
     pub arena_id: Option<u32>,
 
     /// A unique ID for this card in Scryfall’s database.
-    pub id: Guid,
+    pub id: Uuid,
 
     /// A [language](https://scryfall.com/docs/api/languages) code for this printing.
     pub lang: String,
@@ -184,7 +187,7 @@ pub struct ScryCard {
 
     /// A unique ID for this card’s oracle identity. This value is consistent across reprinted card editions, and unique
     /// among different cards with the same name (tokens, Unstable variants, etc).
-    pub oracle_id: Option<Guid>,
+    pub oracle_id: Option<Uuid>,
 
     /// A link to where you can begin paginating all re/prints for this card on Scryfall’s API.
     pub prints_search_uri: Uri,
@@ -228,7 +231,7 @@ pub struct ScryCard {
     pub keywords: Vec<String>,
 
     /// A code for this card’s [layout](https://scryfall.com/docs/api/layouts).
-    pub layout: String,
+    pub layout: Layout,
 
     /// An object describing the legality of this card across play formats. Possible legalities are `legal`,
     /// `not_legal`, `restricted`, and `banned`.
@@ -279,10 +282,10 @@ pub struct ScryCard {
     pub booster: bool,
 
     /// This card’s border color: `black`, `white`, `borderless`, `silver`, or `gold`.
-    pub border_color: String,
+    pub border_color: BorderColor,
 
     ///	The Scryfall ID for the card back design present on this card.
-    pub card_back_id: Option<Guid>,
+    pub card_back_id: Option<Uuid>,
 
     /// This card’s collector number. Note that collector numbers can contain non-numeric characters,
     /// such as letters or `★`.
@@ -308,7 +311,7 @@ pub struct ScryCard {
     pub frame_effects: Option<Vec<String>>,
 
     /// This card’s [frame layout](https://scryfall.com/docs/api/layouts).
-    pub frame: String,
+    pub frame: Frame,
 
     /// True if this card’s artwork is larger than normal.
     pub full_art: bool,
@@ -320,7 +323,7 @@ pub struct ScryCard {
     pub highres_image: bool,
 
     /// A unique identifier for the card artwork that remains consistent across reprints. Newly spoiled cards may not have this field yet.
-    pub illustration_id: Option<Guid>,
+    pub illustration_id: Option<Uuid>,
 
     /// A computer-readable indicator for the state of this card’s image, one of `missing`, `placeholder`, `lowres`,
     /// or `highres_scan`.
@@ -350,13 +353,13 @@ pub struct ScryCard {
 
     // purchase_uris: Object 	/// An object providing URIs to this card’s listing on major marketplaces. (seems missing)
     /// This card’s rarity. One of `common`, `uncommon`, `rare`, `special`, `mythic`, or `bonus`.
-    pub rarity: String,
+    pub rarity: Rarity,
 
     /// An object providing URIs to this card’s listing on other Magic: The Gathering online resources.
     pub related_uris: HashMap<String, Uri>,
 
     /// The date this card was first released.
-    pub released_at: Date,
+    pub released_at: NaiveDate,
 
     /// True if this card is a reprint.
     pub reprint: bool,
@@ -380,7 +383,7 @@ pub struct ScryCard {
     pub set: String,
 
     /// This card’s Set object UUID.
-    pub set_id: Guid,
+    pub set_id: Uuid,
 
     /// True if this card is a Story Spotlight.
     pub story_spotlight: bool,
@@ -392,7 +395,7 @@ pub struct ScryCard {
     pub variation: bool,
 
     /// The printing ID of the printing this card is a variation of.
-    pub variation_of: Option<Guid>,
+    pub variation_of: Option<Uuid>,
 
     /// The security stamp on this card, if any. One of `oval`, `triangle`, `acorn`, `arena`, or `heart`.
     pub security_stamp: Option<String>,
@@ -411,7 +414,7 @@ pub struct ScryCard {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ScryRelatedCard {
     /// An unique ID for this card in Scryfall’s database.
-    pub id: Guid,
+    pub id: Uuid,
 
     /// A content type for this object, always `related_card`.
     pub object: String,
@@ -451,14 +454,14 @@ pub struct ScryCardFace {
 
     /// A unique identifier for the card face artwork that remains consistent across reprints.
     /// Newly spoiled cards may not have this field yet.
-    pub illustration_id: Option<Guid>,
+    pub illustration_id: Option<Uuid>,
 
     /// An object providing URIs to imagery for this face, if this is a double-sided card.
     /// If this card is not double-sided, then the `image_uris` property will be part of the parent object instead.
     pub image_uris: Option<HashMap<String, Uri>>,
 
     /// The layout of this card face, if the card is reversible.
-    pub layout: Option<String>,
+    pub layout: Option<Layout>,
 
     /// This face’s loyalty, if any.
     pub loyalty: Option<String>,
@@ -474,7 +477,7 @@ pub struct ScryCardFace {
     pub object: String,
 
     /// The Oracle ID of this particular face, if the card is reversible.
-    pub oracle_id: Option<Guid>,
+    pub oracle_id: Option<Uuid>,
 
     /// The Oracle text for this face, if any.
     pub oracle_text: Option<String>,
@@ -501,20 +504,195 @@ pub struct ScryCardFace {
     pub watermark: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Guid(String);
-
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Uri(String);
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Colors(Vec<Color>);
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Color(String);
+impl Colors {
+    pub fn iter(&self) -> impl Iterator<Item = &Color> {
+        self.0.iter()
+    }
+}
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Legality(String);
+/// Declares an enum for a Scryfall string value. Values this version does not know yet deserialize into
+/// `Unknown` instead of failing, and serialize back unchanged.
+macro_rules! scryfall_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> $name {
+                match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                match value {
+                    $name::Unknown(value) => value,
+                    value => value.as_str().to_string(),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Date(String);
+scryfall_enum! {
+    /// A color or colorless mana, as in `colors`, `color_identity` and `produced_mana`.
+    Color {
+        White => "W",
+        Blue => "U",
+        Black => "B",
+        Red => "R",
+        Green => "G",
+        Colorless => "C",
+    }
+}
+
+scryfall_enum! {
+    /// The rarities, ordered from `common` to `bonus` as Scryfall orders them.
+    Rarity {
+        Common => "common",
+        Uncommon => "uncommon",
+        Rare => "rare",
+        Special => "special",
+        Mythic => "mythic",
+        Bonus => "bonus",
+    }
+}
+
+scryfall_enum! {
+    /// A card [layout](https://scryfall.com/docs/api/layouts).
+    Layout {
+        Normal => "normal",
+        Split => "split",
+        Flip => "flip",
+        Transform => "transform",
+        ModalDfc => "modal_dfc",
+        Meld => "meld",
+        Leveler => "leveler",
+        Class => "class",
+        Case => "case",
+        Saga => "saga",
+        Adventure => "adventure",
+        Mutate => "mutate",
+        Prototype => "prototype",
+        Battle => "battle",
+        Planar => "planar",
+        Scheme => "scheme",
+        Vanguard => "vanguard",
+        Token => "token",
+        DoubleFacedToken => "double_faced_token",
+        Emblem => "emblem",
+        Augment => "augment",
+        Host => "host",
+        ArtSeries => "art_series",
+        ReversibleCard => "reversible_card",
+    }
+}
+
+scryfall_enum! {
+    BorderColor {
+        Black => "black",
+        White => "white",
+        Borderless => "borderless",
+        Silver => "silver",
+        Gold => "gold",
+    }
+}
+
+scryfall_enum! {
+    /// The [frame](https://scryfall.com/docs/api/frames) of the card, named by the year it was introduced.
+    Frame {
+        Original => "1993",
+        Classic => "1997",
+        Modern => "2003",
+        M15 => "2015",
+        Future => "future",
+    }
+}
+
+scryfall_enum! {
+    Legality {
+        Legal => "legal",
+        NotLegal => "not_legal",
+        Restricted => "restricted",
+        Banned => "banned",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::card;
+
+    #[test]
+    fn reads_the_scryfall_values_into_types() {
+        let card = card(json!({}));
+        assert_eq!(card.rarity, Rarity::Common);
+        assert_eq!(card.layout, Layout::Normal);
+        assert_eq!(card.border_color, BorderColor::Black);
+        assert_eq!(card.legalities["modern"], Legality::Legal);
+        assert_eq!(card.legalities["standard"], Legality::NotLegal);
+        assert_eq!(
+            card.colors
+                .iter()
+                .flat_map(Colors::iter)
+                .collect::<Vec<_>>(),
+            [&Color::Red]
+        );
+        assert!(card.released_at < NaiveDate::from_ymd_opt(2019, 1, 1).unwrap());
+    }
+
+    #[test]
+    fn keeps_the_values_it_does_not_know() {
+        let card = card(json!({
+            "rarity": "timeshifted",
+            "layout": "future_layout",
+            "legalities": { "standard": "suspended" },
+        }));
+        assert_eq!(card.rarity, Rarity::Unknown("timeshifted".to_string()));
+        assert_eq!(card.layout, Layout::Unknown("future_layout".to_string()));
+
+        let data = serde_json::to_value(&card).unwrap();
+        assert_eq!(data["rarity"], "timeshifted");
+        assert_eq!(data["layout"], "future_layout");
+        assert_eq!(data["legalities"]["standard"], "suspended");
+    }
+
+    #[test]
+    fn orders_the_rarities_like_scryfall() {
+        assert!(Rarity::Common < Rarity::Uncommon);
+        assert!(Rarity::Rare < Rarity::Mythic);
+        assert_eq!(Rarity::from("mythic".to_string()).as_str(), "mythic");
+        assert_eq!(Rarity::Mythic.to_string(), "mythic");
+    }
+}
@@ -0,0 +1,685 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use super::fold_name;
+use super::mana::ManaCost;
+use super::model::{Color, Colors, Legality, Rarity, ScryCard};
+
+const WHITE: u8 = 1;
+const BLUE: u8 = 2;
+const BLACK: u8 = 4;
+const RED: u8 = 8;
+const GREEN: u8 = 16;
+
+/// A card search in a subset of the [Scryfall search syntax](https://scryfall.com/docs/syntax).
+///
+/// Terms separated by spaces must all match, `or` between terms matches either side, a leading `-` negates
+/// a term, and parentheses group terms. A word without a keyword searches the card names, also the ones
+/// printed in the preferred language. The keywords are:
+/// - `t:` / `type:` - the type line contains the text
+/// - `o:` / `oracle:` - the Oracle text contains the text, `~` stands for the name of the card
+/// - `kw:` / `keyword:` - the card has the keyword ability
+/// - `c:` / `color:` - the colors, `c:rg` means at least red and green, `c=rg` exactly red and green,
+///   `c<=rg` at most red and green. `c:c` is colorless, `c:m` multicolored.
+/// - `id:` / `identity:` - the color identity, `id:esper` means an identity within Esper
+/// - `cmc` / `mv` - the mana value, e.g. `cmc<=3` or `mv:2`
+/// - `devotion:` - the devotion of the (front face's) mana cost, `devotion:{R}{R}{R}` means at least 3 to red
+/// - `r:` / `rarity:` - the rarity, e.g. `r:mythic` or `r>=rare`
+/// - `s:` / `set:` / `e:` - the set code
+/// - `f:` / `format:` / `legal:` - legal (or restricted) in the format, `banned:` banned in the format
+///
+/// Values with spaces are quoted: `o:"draw a card"`.
+pub enum Query {
+    All(Vec<Query>),
+    Any(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+pub enum Term {
+    Name(String),
+    Type(String),
+    Oracle(String),
+    Keyword(String),
+    Set(String),
+    Legal(String),
+    Banned(String),
+    Color(Comparison, u8),
+    Multicolored,
+    Identity(Comparison, u8),
+    ManaValue(Comparison, f32),
+    Devotion(Comparison, Vec<Color>, u32),
+    Rarity(Comparison, Rarity),
+}
+
+#[derive(Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds<T>(self, value: T, other: T) -> bool
+    where
+        T: PartialOrd,
+    {
+        match self {
+            Comparison::Equal => value == other,
+            Comparison::NotEqual => value != other,
+            Comparison::Less => value < other,
+            Comparison::LessOrEqual => value <= other,
+            Comparison::Greater => value > other,
+            Comparison::GreaterOrEqual => value >= other,
+        }
+    }
+
+    /// Compares color sets by inclusion: `<=` is a subset, `>=` a superset.
+    fn holds_for_colors(self, colors: u8, other: u8) -> bool {
+        let subset = colors & !other == 0;
+        let superset = colors & other == other;
+        match self {
+            Comparison::Equal => colors == other,
+            Comparison::NotEqual => colors != other,
+            Comparison::Less => subset && colors != other,
+            Comparison::LessOrEqual => subset,
+            Comparison::Greater => superset && colors != other,
+            Comparison::GreaterOrEqual => superset,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = Box<dyn Error>;
+
+    fn from_str(query: &str) -> Result<Query, Self::Err> {
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            position: 0,
+        };
+        let query = parser.parse_any()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            Some(c) => Err(format!("Unexpected '{}' in the search query", c).into()),
+            None => Ok(query),
+        }
+    }
+}
+
+impl Query {
+    /// Checks the card against the query. A name term also matches the name of the card printed in the
+    /// preferred language, if there is one.
+    pub fn matches(&self, card: &ScryCard, printed_name: Option<&str>) -> bool {
+        match self {
+            Query::All(queries) => queries
+                .iter()
+                .all(|query| query.matches(card, printed_name)),
+            Query::Any(queries) => queries
+                .iter()
+                .any(|query| query.matches(card, printed_name)),
+            Query::Not(query) => !query.matches(card, printed_name),
+            Query::Term(term) => term.matches(card, printed_name),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, card: &ScryCard, printed_name: Option<&str>) -> bool {
+        match self {
+            Term::Name(name) => {
+                fold_name(&card.name).contains(name)
+                    || card
+                        .printed_name
+                        .as_deref()
+                        .into_iter()
+                        .chain(printed_name)
+                        .any(|n| fold_name(n).contains(name))
+            }
+            Term::Type(text) => {
+                type_lines(card).any(|type_line| fold_name(type_line).contains(text))
+            }
+            Term::Oracle(text) => oracle_texts(card).any(|(name, oracle_text)| {
+                fold_name(oracle_text).contains(&text.replace('~', &fold_name(name)))
+            }),
+            Term::Keyword(keyword) => card.keywords.iter().any(|k| fold_name(k) == *keyword),
+            Term::Set(set) => card.set.eq_ignore_ascii_case(set),
+            Term::Legal(format) => matches!(
+                card.legalities.get(format),
+                Some(Legality::Legal) | Some(Legality::Restricted)
+            ),
+            Term::Banned(format) => card.legalities.get(format) == Some(&Legality::Banned),
+            Term::Color(comparison, colors) => {
+                comparison.holds_for_colors(card_colors(card), *colors)
+            }
+            Term::Multicolored => card_colors(card).count_ones() > 1,
+            Term::Identity(comparison, colors) => {
+                comparison.holds_for_colors(color_mask(Some(&card.color_identity)), *colors)
+            }
+            Term::ManaValue(comparison, value) => {
+                let cmc = card.cmc.or_else(|| {
+                    card.card_faces
+                        .as_ref()
+                        .and_then(|faces| faces.first())
+                        .and_then(|face| face.cmc)
+                });
+                cmc.is_some_and(|cmc| comparison.holds(cmc, *value))
+            }
+            Term::Devotion(comparison, colors, devotion) => {
+                let card_devotion = match card.mana_costs() {
+                    Ok(costs) => costs.first().map_or(0, |cost| cost.devotion(colors)),
+                    Err(_) => 0,
+                };
+                comparison.holds(card_devotion, *devotion)
+            }
+            Term::Rarity(comparison, rarity) => {
+                !matches!(card.rarity, Rarity::Unknown(_)) && comparison.holds(&card.rarity, rarity)
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Checks for the `or` keyword, which has to stand on its own, so `orc` is still a name.
+    fn at_or(&self) -> bool {
+        let word = self
+            .chars
+            .iter()
+            .skip(self.position)
+            .take(2)
+            .collect::<String>();
+        let after = self.chars.get(self.position + 2);
+        let stands_alone = match after {
+            Some(&c) => c.is_whitespace() || c == '(',
+            None => true,
+        };
+        word.eq_ignore_ascii_case("or") && stands_alone
+    }
+
+    fn parse_any(&mut self) -> Result<Query, Box<dyn Error>> {
+        let mut queries = vec![self.parse_all()?];
+        while self.at_or() {
+            self.position += 2;
+            queries.push(self.parse_all()?);
+        }
+        Ok(match queries.len() {
+            1 => queries.remove(0),
+            _ => Query::Any(queries),
+        })
+    }
+
+    fn parse_all(&mut self) -> Result<Query, Box<dyn Error>> {
+        let mut queries = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(')') => break,
+                Some(_) if self.at_or() => break,
+                Some(_) => queries.push(self.parse_negation()?),
+            }
+        }
+        Ok(match queries.len() {
+            0 => return Err("Expected a search term".into()),
+            1 => queries.remove(0),
+            _ => Query::All(queries),
+        })
+    }
+
+    fn parse_negation(&mut self) -> Result<Query, Box<dyn Error>> {
+        match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                Ok(Query::Not(Box::new(self.parse_negation()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let query = self.parse_any()?;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err("Missing ')' in the search query".into());
+                }
+                self.position += 1;
+                Ok(query)
+            }
+            _ => self.parse_term(),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Query, Box<dyn Error>> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.position += 1;
+        }
+        let keyword = self.chars[start..self.position].iter().collect::<String>();
+
+        let operator = ["!=", "<=", ">=", ":", "=", "<", ">"]
+            .into_iter()
+            .find(|operator| {
+                operator
+                    .chars()
+                    .enumerate()
+                    .all(|(i, c)| self.chars.get(self.position + i) == Some(&c))
+            });
+
+        match operator {
+            Some(operator) if !keyword.is_empty() => {
+                self.position += operator.len();
+                let value = self.parse_value()?;
+                if value.is_empty() {
+                    return Err(format!("Missing value after '{}{}'", keyword, operator).into());
+                }
+                Ok(Query::Term(parse_keyword(&keyword, operator, &value)?))
+            }
+            _ => {
+                self.position = start;
+                let name = self.parse_value()?;
+                if name.is_empty() {
+                    return Err("Missing a card name in the search query".into());
+                }
+                Ok(Query::Term(Term::Name(fold_name(&name))))
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, Box<dyn Error>> {
+        let mut value = String::new();
+        if self.peek() == Some('"') {
+            self.position += 1;
+            loop {
+                match self.peek() {
+                    Some('"') => break,
+                    Some(c) => value.push(c),
+                    None => return Err("Missing closing '\"' in the search query".into()),
+                }
+                self.position += 1;
+            }
+            self.position += 1;
+        } else {
+            while let Some(c) = self.peek().filter(|&c| !c.is_whitespace() && c != ')') {
+                value.push(c);
+                self.position += 1;
+            }
+        }
+        Ok(value)
+    }
+}
+
+fn parse_keyword(keyword: &str, operator: &str, value: &str) -> Result<Term, Box<dyn Error>> {
+    let keyword = keyword.to_lowercase();
+    let text = || match operator {
+        ":" | "=" => Ok(fold_name(value)),
+        _ => Err(format!("'{}' can only be used with ':'", keyword)),
+    };
+
+    let term = match keyword.as_str() {
+        "t" | "type" => Term::Type(text()?),
+        "o" | "oracle" => Term::Oracle(text()?),
+        "kw" | "keyword" => Term::Keyword(text()?),
+        "s" | "set" | "e" | "edition" => Term::Set(text()?),
+        "f" | "format" | "legal" => Term::Legal(text()?),
+        "banned" => Term::Banned(text()?),
+        "c" | "color" => match value.to_lowercase().as_str() {
+            "m" | "multicolor" => Term::Multicolored,
+            value => {
+                let colors = parse_colors(value)?;
+                // every card has at least no colors, so like on Scryfall `c:c` means exactly colorless
+                let comparison = match comparison(operator, Comparison::GreaterOrEqual) {
+                    Comparison::GreaterOrEqual if colors == 0 => Comparison::Equal,
+                    comparison => comparison,
+                };
+                Term::Color(comparison, colors)
+            }
+        },
+        "id" | "identity" => Term::Identity(
+            comparison(operator, Comparison::LessOrEqual),
+            parse_colors(&value.to_lowercase())?,
+        ),
+        "cmc" | "mv" | "manavalue" => Term::ManaValue(
+            comparison(operator, Comparison::Equal),
+            value
+                .parse()
+                .map_err(|_| format!("Not a mana value: {}", value))?,
+        ),
+        "devotion" => {
+            let cost = value.parse::<ManaCost>()?;
+            let mut colors = cost
+                .symbols()
+                .iter()
+                .flat_map(|symbol| symbol.colors())
+                .collect::<Vec<_>>();
+            colors.sort();
+            colors.dedup();
+            if colors.is_empty() {
+                return Err(format!("Devotion needs colored mana symbols: {}", value).into());
+            }
+            Term::Devotion(
+                comparison(operator, Comparison::GreaterOrEqual),
+                colors,
+                cost.symbols().len() as u32,
+            )
+        }
+        "r" | "rarity" => Term::Rarity(
+            comparison(operator, Comparison::Equal),
+            parse_rarity(&value.to_lowercase()).ok_or(format!("Unknown rarity: {}", value))?,
+        ),
+        _ => return Err(format!("Unknown search keyword: {}", keyword).into()),
+    };
+    Ok(term)
+}
+
+fn comparison(operator: &str, colon: Comparison) -> Comparison {
+    match operator {
+        "=" => Comparison::Equal,
+        "!=" => Comparison::NotEqual,
+        "<" => Comparison::Less,
+        "<=" => Comparison::LessOrEqual,
+        ">" => Comparison::Greater,
+        ">=" => Comparison::GreaterOrEqual,
+        _ => colon,
+    }
+}
+
+/// Parses color letters (`wubrg`, `c` for colorless) or a color, guild, shard or wedge name.
+fn parse_colors(value: &str) -> Result<u8, Box<dyn Error>> {
+    let letters = match value {
+        "white" => "w",
+        "blue" => "u",
+        "black" => "b",
+        "red" => "r",
+        "green" => "g",
+        "colorless" => "c",
+        "azorius" => "wu",
+        "dimir" => "ub",
+        "rakdos" => "br",
+        "gruul" => "rg",
+        "selesnya" => "gw",
+        "orzhov" => "wb",
+        "izzet" => "ur",
+        "golgari" => "bg",
+        "boros" => "rw",
+        "simic" => "gu",
+        "bant" => "gwu",
+        "esper" => "wub",
+        "grixis" => "ubr",
+        "jund" => "brg",
+        "naya" => "rgw",
+        "abzan" => "wbg",
+        "jeskai" => "urw",
+        "sultai" => "bgu",
+        "mardu" => "rwb",
+        "temur" => "gur",
+        letters => letters,
+    };
+
+    letters.chars().try_fold(0, |colors, c| match c {
+        'w' => Ok(colors | WHITE),
+        'u' => Ok(colors | BLUE),
+        'b' => Ok(colors | BLACK),
+        'r' => Ok(colors | RED),
+        'g' => Ok(colors | GREEN),
+        'c' => Ok(colors),
+        _ => Err(format!("Unknown color: {}", value).into()),
+    })
+}
+
+fn color_mask(colors: Option<&Colors>) -> u8 {
+    colors
+        .into_iter()
+        .flat_map(|colors| colors.iter())
+        .fold(0, |mask, color| match color {
+            Color::White => mask | WHITE,
+            Color::Blue => mask | BLUE,
+            Color::Black => mask | BLACK,
+            Color::Red => mask | RED,
+            Color::Green => mask | GREEN,
+            _ => mask,
+        })
+}
+
+/// The colors of the card, or of its faces if the colors are only given per face.
+fn card_colors(card: &ScryCard) -> u8 {
+    match (&card.colors, &card.card_faces) {
+        (None, Some(faces)) => faces
+            .iter()
+            .fold(0, |mask, face| mask | color_mask(face.colors.as_ref())),
+        (colors, _) => color_mask(colors.as_ref()),
+    }
+}
+
+fn type_lines(card: &ScryCard) -> impl Iterator<Item = &String> {
+    card.type_line.iter().chain(
+        card.card_faces
+            .iter()
+            .flatten()
+            .filter_map(|face| face.type_line.as_ref()),
+    )
+}
+
+/// The Oracle texts of the card and its faces, with the name `~` stands for in each.
+fn oracle_texts(card: &ScryCard) -> impl Iterator<Item = (&String, &String)> {
+    card.oracle_text
+        .iter()
+        .map(move |oracle_text| (&card.name, oracle_text))
+        .chain(card.card_faces.iter().flatten().filter_map(|face| {
+            face.oracle_text
+                .as_ref()
+                .map(|oracle_text| (&face.name, oracle_text))
+        }))
+}
+
+/// Parses a rarity name or its first letter. Comparisons use the order of `Rarity`, which is Scryfall's.
+fn parse_rarity(value: &str) -> Option<Rarity> {
+    match value {
+        "c" | "common" => Some(Rarity::Common),
+        "u" | "uncommon" => Some(Rarity::Uncommon),
+        "r" | "rare" => Some(Rarity::Rare),
+        "s" | "special" => Some(Rarity::Special),
+        "m" | "mythic" => Some(Rarity::Mythic),
+        "b" | "bonus" => Some(Rarity::Bonus),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::card;
+
+    fn cards() -> Vec<ScryCard> {
+        vec![
+            card(json!({})),
+            card(json!({
+                "name": "Ornithopter",
+                "mana_cost": "{0}",
+                "cmc": 0.0,
+                "type_line": "Artifact Creature — Thopter",
+                "oracle_text": "Flying",
+                "keywords": ["Flying"],
+                "colors": [],
+                "color_identity": [],
+                "rarity": "uncommon",
+                "set": "m21",
+            })),
+            card(json!({
+                "name": "Bloodbraid Elf",
+                "mana_cost": "{2}{R}{G}",
+                "cmc": 4.0,
+                "type_line": "Creature — Elf Berserker",
+                "oracle_text": "Haste\nCascade",
+                "keywords": ["Haste", "Cascade"],
+                "colors": ["R", "G"],
+                "color_identity": ["R", "G"],
+                "rarity": "uncommon",
+                "legalities": {"modern": "banned", "pioneer": "not_legal"},
+            })),
+            card(json!({
+                "name": "Nykthos, Shrine to Nyx",
+                "mana_cost": null,
+                "cmc": 0.0,
+                "type_line": "Legendary Land",
+                "oracle_text": "{2}, {T}: Choose a color. Add an amount of mana of that color equal to your devotion to that color.",
+                "colors": [],
+                "color_identity": [],
+                "rarity": "rare",
+            })),
+            card(json!({
+                "name": "Burning-Tree Emissary",
+                "mana_cost": "{R/G}{R/G}",
+                "cmc": 2.0,
+                "type_line": "Creature — Human Shaman",
+                "oracle_text": "When Burning-Tree Emissary enters the battlefield, add {R}{G}.",
+                "colors": ["R", "G"],
+                "color_identity": ["R", "G"],
+                "rarity": "mythic",
+            })),
+        ]
+    }
+
+    fn search(query: &str) -> Vec<String> {
+        let query = query.parse::<Query>().unwrap();
+        cards()
+            .into_iter()
+            .filter(|card| query.matches(card, None))
+            .map(|card| card.name)
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        match query.parse::<Query>() {
+            Ok(_) => panic!("'{}' should not parse", query),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_the_colorless_cards() {
+        assert_eq!(search("c:c"), ["Ornithopter", "Nykthos, Shrine to Nyx"]);
+        assert_eq!(search("c:colorless"), search("c:c"));
+        assert_eq!(search("c=c"), search("c:c"));
+        assert_eq!(search("id:c"), search("c:c"));
+    }
+
+    #[test]
+    fn compares_the_colors() {
+        assert_eq!(search("c:m"), ["Bloodbraid Elf", "Burning-Tree Emissary"]);
+        assert_eq!(
+            search("c:r"),
+            [
+                "Lightning Strike",
+                "Bloodbraid Elf",
+                "Burning-Tree Emissary"
+            ]
+        );
+        assert_eq!(search("c=r"), ["Lightning Strike"]);
+        assert_eq!(search("c<=rg").len(), 5);
+        assert_eq!(search("c<rg").len(), 3);
+        assert_eq!(search("c:gruul"), search("c>=rg"));
+        assert_eq!(
+            search("id:r"),
+            ["Lightning Strike", "Ornithopter", "Nykthos, Shrine to Nyx"]
+        );
+        assert_eq!(
+            search("id>=rg"),
+            ["Bloodbraid Elf", "Burning-Tree Emissary"]
+        );
+    }
+
+    #[test]
+    fn compares_the_mana_value_devotion_and_rarity() {
+        assert_eq!(search("cmc>=4"), ["Bloodbraid Elf"]);
+        assert_eq!(search("mv=0"), ["Ornithopter", "Nykthos, Shrine to Nyx"]);
+        assert_eq!(search("cmc<2").len(), 2);
+        // a hybrid symbol counts for the devotion to either of its colors
+        assert_eq!(search("devotion:{R}{R}"), ["Burning-Tree Emissary"]);
+        assert_eq!(
+            search("devotion:{G}"),
+            ["Bloodbraid Elf", "Burning-Tree Emissary"]
+        );
+        assert_eq!(search("r:u"), ["Ornithopter", "Bloodbraid Elf"]);
+        assert_eq!(
+            search("r>=rare"),
+            ["Nykthos, Shrine to Nyx", "Burning-Tree Emissary"]
+        );
+    }
+
+    #[test]
+    fn searches_names_types_texts_sets_and_legalities() {
+        assert_eq!(search("elf"), ["Bloodbraid Elf"]);
+        assert_eq!(search("\"shrine to\""), ["Nykthos, Shrine to Nyx"]);
+        assert_eq!(search("t:legendary"), ["Nykthos, Shrine to Nyx"]);
+        assert_eq!(search("o:\"when ~ enters\""), ["Burning-Tree Emissary"]);
+        assert_eq!(search("kw:cascade"), ["Bloodbraid Elf"]);
+        assert_eq!(search("s:M21"), ["Ornithopter"]);
+        assert_eq!(search("banned:modern"), ["Bloodbraid Elf"]);
+        assert_eq!(search("f:modern").len(), 4);
+    }
+
+    #[test]
+    fn combines_the_terms() {
+        assert_eq!(
+            search("c:r t:creature"),
+            ["Bloodbraid Elf", "Burning-Tree Emissary"]
+        );
+        assert!(search("-c:r -c:c").is_empty());
+        assert_eq!(
+            search("strike or ornithopter"),
+            ["Lightning Strike", "Ornithopter"]
+        );
+        assert_eq!(
+            search("c:r (t:instant or cmc>3)"),
+            ["Lightning Strike", "Bloodbraid Elf"]
+        );
+        assert!(search("-(c:r or c:c)").is_empty());
+        // `or` only stands on its own, here it starts a name
+        assert_eq!(search("ornithopter"), ["Ornithopter"]);
+    }
+
+    #[test]
+    fn matches_the_name_in_the_preferred_language() {
+        let query = "blitz".parse::<Query>().unwrap();
+        let card = card(json!({}));
+        assert!(!query.matches(&card, None));
+        assert!(query.matches(&card, Some("Blitzschlag")));
+    }
+
+    #[test]
+    fn fails_on_unknown_keywords_and_bad_values() {
+        assert_eq!(error("foo:bar"), "Unknown search keyword: foo");
+        assert_eq!(error("c:x"), "Unknown color: x");
+        assert_eq!(error("cmc>=many"), "Not a mana value: many");
+        assert_eq!(error("r:epic"), "Unknown rarity: epic");
+        assert_eq!(
+            error("devotion:{2}"),
+            "Devotion needs colored mana symbols: {2}"
+        );
+        assert_eq!(error("t>=creature"), "'t' can only be used with ':'");
+        assert_eq!(error("t:"), "Missing value after 't:'");
+        assert_eq!(error("(c:r"), "Missing ')' in the search query");
+        assert_eq!(error("c:r)"), "Unexpected ')' in the search query");
+        assert_eq!(
+            error("o:\"draw"),
+            "Missing closing '\"' in the search query"
+        );
+        assert_eq!(error("\"\""), "Missing a card name in the search query");
+        assert_eq!(error("or"), "Expected a search term");
+    }
+}
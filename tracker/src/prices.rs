@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use chrono::NaiveDate;
+
+use crate::collector::model::Collection;
+use crate::mtgadb::model::ScryCard;
+use crate::mtgadb::{MtgaDb, SetFilter};
+
+/// How the prices of the cards moved between two days.
+struct Movement<'a> {
+    /// The cards with a price on both days, the biggest moves first.
+    moved: Vec<(&'a ScryCard, f64, f64)>,
+    /// The cards without a price on one of the days, by name.
+    unpriced: Vec<&'a ScryCard>,
+    /// The number of cards that were not in the card database on the first day.
+    new_cards: usize,
+}
+
+/// The owned cards by the prices of a day.
+struct Value<'a> {
+    /// The cards with a price and the number of copies owned, the most valuable first.
+    owned: Vec<(&'a ScryCard, u32, f64)>,
+    /// The cards without a price, by name.
+    unpriced: Vec<(&'a ScryCard, u32)>,
+    /// The number of owned cards that are not in the card database.
+    unmapped: usize,
+}
+
+/// Prints the cards of the sets whose price moved the most between two days, and the totals per set.
+pub fn print_movement(
+    database: &MtgaDb,
+    set_filter: &SetFilter,
+    currency: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    limit: usize,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    let Movement {
+        moved: movements,
+        unpriced,
+        new_cards,
+    } = movement(
+        database,
+        set_filter,
+        &database.prices_on(currency, from)?,
+        &database.prices_on(currency, to)?,
+    );
+
+    println!("Price movement in {} from {} to {}:", currency, from, to);
+    for (card, old, new) in movements
+        .iter()
+        .filter(|(_, old, new)| old != new)
+        .take(limit)
+    {
+        println!(
+            "{:<30} {:<5} {:>5} {:>9.2} {:>9.2} {:>+9.2} {}",
+            card.name,
+            card.set,
+            card.collector_number,
+            old,
+            new,
+            new - old,
+            percent_change(*old, *new)
+        );
+    }
+
+    let mut sets = HashMap::new();
+    for (card, old, new) in &movements {
+        let totals = sets.entry(card.set.as_str()).or_insert((0.0, 0.0));
+        totals.0 += old;
+        totals.1 += new;
+    }
+    let mut sets = sets.into_iter().collect::<Vec<_>>();
+    sets.sort_by(|a, b| a.0.cmp(b.0));
+    println!("Total per set:");
+    for (set, (old, new)) in sets {
+        println!(
+            "{:>10} {:>9.2} {:>9.2} {:>+9.2} {}",
+            set,
+            old,
+            new,
+            new - old,
+            percent_change(old, new)
+        );
+    }
+
+    println!(
+        "{} cards have a {} price on both days, {} cards are missing one.",
+        movements.len(),
+        currency,
+        unpriced.len()
+    );
+    if verbose {
+        for card in unpriced {
+            println!("  - {} ({} {})", card.name, card.set, card.collector_number);
+        }
+    }
+    if new_cards > 0 {
+        println!(
+            "{} cards were not in the card database yet on {}.",
+            new_cards, from
+        );
+    }
+    Ok(())
+}
+
+/// Prints the owned cards worth the most by the prices of the day, and the value of the whole collection.
+pub fn print_value(
+    database: &MtgaDb,
+    collection: &Collection,
+    currency: &str,
+    date: NaiveDate,
+    limit: usize,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    let Value {
+        owned,
+        unpriced,
+        unmapped,
+    } = value(database, collection, &database.prices_on(currency, date)?);
+
+    println!(
+        "Paper value of the owned cards in {} on {}:",
+        currency, date
+    );
+    for (card, count, price) in owned.iter().take(limit) {
+        println!(
+            "{:<30} {:<5} {:>5} x{} {:>9.2} {:>9.2}",
+            card.name,
+            card.set,
+            card.collector_number,
+            count,
+            price,
+            price * *count as f64
+        );
+    }
+    let total: f64 = owned
+        .iter()
+        .map(|(_, count, price)| price * *count as f64)
+        .sum();
+    println!(
+        "{} different cards are worth {:.2} {}.",
+        owned.len(),
+        total,
+        currency
+    );
+
+    if !unpriced.is_empty() {
+        println!(
+            "{} different owned cards have no {} price.",
+            unpriced.len(),
+            currency
+        );
+        if verbose {
+            for (card, count) in unpriced {
+                println!(
+                    "  - {} ({} {}) x{}",
+                    card.name, card.set, card.collector_number, count
+                );
+            }
+        }
+    }
+    if unmapped > 0 {
+        println!(
+            "{} different owned cards are not in the card database, see the unmapped command.",
+            unmapped
+        );
+    }
+    Ok(())
+}
+
+/// Compares the prices of the cards of the sets on two days.
+fn movement<'a>(
+    database: &'a MtgaDb,
+    set_filter: &SetFilter,
+    old_prices: &HashMap<u32, Option<f64>>,
+    new_prices: &HashMap<u32, Option<f64>>,
+) -> Movement<'a> {
+    let mut movement = Movement {
+        moved: Vec::new(),
+        unpriced: Vec::new(),
+        new_cards: 0,
+    };
+    for (arena_id, card) in database.arena_cards() {
+        if !set_filter.includes(&card.set) {
+            continue;
+        }
+        match (old_prices.get(arena_id), new_prices.get(arena_id)) {
+            (Some(Some(old)), Some(Some(new))) => movement.moved.push((card, *old, *new)),
+            (None, _) => movement.new_cards += 1,
+            _ => movement.unpriced.push(card),
+        }
+    }
+    movement.moved.sort_by(|a, b| {
+        (b.2 - b.1)
+            .abs()
+            .total_cmp(&(a.2 - a.1).abs())
+            .then_with(|| a.0.name.cmp(&b.0.name))
+    });
+    movement.unpriced.sort_by(|a, b| a.name.cmp(&b.name));
+    movement
+}
+
+/// Prices the owned cards.
+fn value<'a>(
+    database: &'a MtgaDb,
+    collection: &Collection,
+    prices: &HashMap<u32, Option<f64>>,
+) -> Value<'a> {
+    let mut value = Value {
+        owned: Vec::new(),
+        unpriced: Vec::new(),
+        unmapped: 0,
+    };
+    for (arena_id, count) in collection.iter() {
+        match (database.card(arena_id), prices.get(&arena_id)) {
+            (Some(card), Some(Some(price))) => value.owned.push((card, count, *price)),
+            (Some(card), _) => value.unpriced.push((card, count)),
+            (None, _) => value.unmapped += 1,
+        }
+    }
+    value.owned.sort_by(|a, b| {
+        (b.2 * b.1 as f64)
+            .total_cmp(&(a.2 * a.1 as f64))
+            .then_with(|| a.0.name.cmp(&b.0.name))
+    });
+    value.unpriced.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    value
+}
+
+/// The change in percent, or nothing if there was no price to compare with.
+fn percent_change(old: f64, new: f64) -> String {
+    if old > 0.0 {
+        format!("{:>+7.1}%", (new - old) / old * 100.0)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::card;
+
+    fn database() -> MtgaDb {
+        MtgaDb::from_cards(
+            vec![
+                card(json!({})),
+                card(json!({ "arena_id": 70004, "name": "Brazen Borrower", "set": "eld" })),
+                card(json!({ "arena_id": 70006, "name": "Plains", "set": "dmu" })),
+                card(json!({ "arena_id": 70008, "name": "Shock", "set": "m19" })),
+            ],
+            vec![],
+        )
+    }
+
+    fn names<'a>(cards: impl IntoIterator<Item = &'a ScryCard>) -> Vec<&'a str> {
+        cards.into_iter().map(|card| card.name.as_str()).collect()
+    }
+
+    #[test]
+    fn compares_the_cards_priced_on_both_days() {
+        let database = database();
+        let old = HashMap::from([(70002, Some(1.0)), (70004, Some(2.0)), (70006, None)]);
+        let new = HashMap::from([
+            (70002, Some(1.5)),
+            (70004, Some(1.0)),
+            (70006, Some(0.1)),
+            (70008, Some(0.5)),
+        ]);
+
+        let moved = movement(&database, &SetFilter::AllArenaSets, &old, &new);
+        assert_eq!(
+            moved
+                .moved
+                .iter()
+                .map(|(card, old, new)| (card.name.as_str(), *old, *new))
+                .collect::<Vec<_>>(),
+            [
+                ("Brazen Borrower", 2.0, 1.0),
+                ("Lightning Strike", 1.0, 1.5)
+            ]
+        );
+        assert_eq!(names(moved.unpriced), ["Plains"]);
+        assert_eq!(moved.new_cards, 1);
+
+        let moved = movement(&database, &SetFilter::only(["M19"]), &old, &new);
+        assert_eq!(
+            names(moved.moved.iter().map(|(card, _, _)| *card)),
+            ["Lightning Strike"]
+        );
+        assert!(moved.unpriced.is_empty());
+        assert_eq!(moved.new_cards, 1);
+    }
+
+    #[test]
+    fn counts_a_card_without_a_price_on_the_last_day_as_unpriced() {
+        let database = database();
+        let old = HashMap::from([(70002, Some(1.0)), (70004, Some(2.0))]);
+        let new = HashMap::from([(70002, None)]);
+
+        let moved = movement(&database, &SetFilter::only(["m19", "eld"]), &old, &new);
+        assert!(moved.moved.is_empty());
+        assert_eq!(
+            names(moved.unpriced),
+            ["Brazen Borrower", "Lightning Strike"]
+        );
+        assert_eq!(moved.new_cards, 1);
+    }
+
+    #[test]
+    fn values_the_owned_copies() {
+        let database = database();
+        let collection = [(70002, 4), (70004, 1), (70006, 2), (70008, 1), (99999, 1)]
+            .into_iter()
+            .collect();
+        let prices = HashMap::from([(70002, Some(1.0)), (70004, Some(3.0)), (70006, None)]);
+
+        let value = value(&database, &collection, &prices);
+        assert_eq!(
+            value
+                .owned
+                .iter()
+                .map(|(card, count, price)| (card.name.as_str(), *count, *price))
+                .collect::<Vec<_>>(),
+            [("Lightning Strike", 4, 1.0), ("Brazen Borrower", 1, 3.0)]
+        );
+        assert_eq!(
+            value
+                .unpriced
+                .iter()
+                .map(|(card, count)| (card.name.as_str(), *count))
+                .collect::<Vec<_>>(),
+            [("Plains", 2), ("Shock", 1)]
+        );
+        assert_eq!(value.unmapped, 1);
+    }
+}
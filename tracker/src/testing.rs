@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::mtgadb::model::ScryCard;
+
+/// A card built from the Lightning Strike of the Scryfall fixture, with the given fields replaced.
+pub fn card(fields: serde_json::Value) -> ScryCard {
+    let cards: Vec<serde_json::Value> =
+        serde_json::from_str(include_str!("../tests/fixtures/scryfall.json")).unwrap();
+    let mut card = cards[0].clone();
+    for (field, value) in fields.as_object().expect("the fields should be an object") {
+        card[field] = value.clone();
+    }
+    serde_json::from_value(card).unwrap()
+}
+
+/// A file in the temporary directory, removed again when the test ends.
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    /// A file named after the test, so tests running in parallel do not share one.
+    pub fn new(name: &str) -> TempFile {
+        let path = std::env::temp_dir().join(format!("tracker-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}